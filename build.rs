@@ -3,18 +3,25 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
+#[path = "src/sitemap.rs"]
+mod sitemap;
+#[path = "src/utils/slug.rs"]
+mod slug;
+
 fn main() {
     // Tell Cargo to rerun if git history, posts, or config change
     println!("cargo:rerun-if-changed=.git/HEAD");
     println!("cargo:rerun-if-changed=.git/refs");
     println!("cargo:rerun-if-changed=content/posts");
     println!("cargo:rerun-if-changed=site.toml");
+    println!("cargo:rerun-if-changed=syntaxes");
 
     let out_dir = env::var("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("generated_metadata.rs");
 
     let mut metadata = Vec::new();
     let mut sitemap_entries = Vec::new();
+    let mut series_slugs = Vec::new();
 
     // Iterate over .org files in content/posts/
     let posts_dir = Path::new("content/posts");
@@ -23,12 +30,21 @@ fn main() {
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("org") {
                 if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
+                    // Unlisted, protected, noindex and cross-posted posts stay out of the sitemap
+                    let listed = in_sitemap(&path);
                     if let Some(date) = get_last_modified_date(&path) {
                         metadata.push(format!("    (\"{}\", \"{}\"),", filename, date));
 
                         // Generate slug from filename (remove .org extension)
                         let slug = filename.trim_end_matches(".org");
-                        sitemap_entries.push((slug.to_string(), date));
+                        if listed {
+                            sitemap_entries.push((slug.to_string(), date));
+                        }
+                    }
+                    if let Some(series) = read_series_slug(&path).filter(|_| listed) {
+                        if !series_slugs.contains(&series) {
+                            series_slugs.push(series);
+                        }
                     }
                 }
             }
@@ -45,8 +61,11 @@ fn main() {
     // Generate embedded site config
     generate_embedded_config(&out_dir);
 
+    // Serialize the syntax set used for code highlighting
+    generate_syntax_dump(&out_dir);
+
     // Generate sitemap.xml and robots.txt
-    generate_sitemap(&sitemap_entries);
+    generate_sitemap(&sitemap_entries, &series_slugs, &read_author_ids());
     generate_robots_txt();
 }
 
@@ -73,7 +92,48 @@ fn get_last_modified_date(path: &Path) -> Option<String> {
     }
 }
 
-fn generate_sitemap(posts: &[(String, String)]) {
+fn generate_syntax_dump(out_dir: &str) {
+    // Start from syntect's bundled syntaxes and merge in our own from syntaxes/
+    let mut builder = syntect::parsing::SyntaxSet::load_defaults_newlines().into_builder();
+    builder
+        .add_from_folder("syntaxes", true)
+        .expect("Failed to load syntax definitions from syntaxes/");
+    let syntax_set = builder.build();
+
+    // Uncompressed so startup only has to deserialize, not inflate
+    let dump_path = Path::new(out_dir).join("syntaxes.packdump");
+    syntect::dumps::dump_to_uncompressed_file(&syntax_set, &dump_path)
+        .expect("Failed to write syntaxes.packdump");
+    println!("Embedded {} syntax definitions", syntax_set.syntaxes().len());
+}
+
+/// Whether a post belongs in the sitemap, see `sitemap::in_sitemap`
+fn in_sitemap(path: &Path) -> bool {
+    let domain = env::var("SITE_DOMAIN").unwrap_or_default();
+    fs::read_to_string(path).is_ok_and(|content| sitemap::in_sitemap(&content, &domain))
+}
+
+/// The value of the first `#+KEY:` line in a post, `key` in lowercase
+fn read_keyword(path: &Path, key: &str) -> Option<String> {
+    sitemap::read_keyword(&fs::read_to_string(path).ok()?, key)
+}
+
+/// Slug of the post's `#+SERIES:`, the same `slugify` the `/series/{slug}` route uses
+fn read_series_slug(path: &Path) -> Option<String> {
+    let slug = slug::slugify(&read_keyword(path, "series")?);
+    (!slug.is_empty()).then_some(slug)
+}
+
+/// Author ids from site.toml, the same file `generate_embedded_config` embeds, so the
+/// runtime serves a page for each of them
+fn read_author_ids() -> Vec<String> {
+    let Ok(content) = fs::read_to_string("site.toml") else {
+        return Vec::new();
+    };
+    sitemap::author_ids(&content).expect("Failed to parse site.toml")
+}
+
+fn generate_sitemap(posts: &[(String, String)], series: &[String], authors: &[String]) {
     let domain = match env::var("SITE_DOMAIN") {
         Ok(d) => d,
         Err(_) => {
@@ -108,6 +168,32 @@ fn generate_sitemap(posts: &[(String, String)]) {
         ));
     }
 
+    // Add series landing pages
+    for slug in series {
+        xml.push_str(&format!(
+            r#"  <url>
+    <loc>{}/series/{}</loc>
+    <changefreq>monthly</changefreq>
+    <priority>0.6</priority>
+  </url>
+"#,
+            domain, slug
+        ));
+    }
+
+    // Add author pages
+    for id in authors {
+        xml.push_str(&format!(
+            r#"  <url>
+    <loc>{}/authors/{}</loc>
+    <changefreq>monthly</changefreq>
+    <priority>0.5</priority>
+  </url>
+"#,
+            domain, id
+        ));
+    }
+
     xml.push_str("</urlset>\n");
 
     // Write sitemap.xml to static/ folder so it gets embedded
@@ -135,72 +221,18 @@ fn generate_robots_txt() {
     println!("Generated robots.txt for domain: {}", domain);
 }
 
+/// Copy site.toml into the binary, so a deploy without the file keeps every section of it
 fn generate_embedded_config(out_dir: &str) {
-    // Read site.toml at build time
     let config_path = Path::new("site.toml");
 
-    let (site_name, site_domain, site_description, default_theme) = if config_path.exists() {
-        let content = fs::read_to_string(config_path)
-            .expect("Failed to read site.toml");
-
-        // Parse the TOML manually to avoid adding toml as a build dependency
-        let mut name = String::from("Blog");
-        let mut domain = String::new();
-        let mut description = String::from("A technical blog");
-        let mut theme = String::from("system");
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.starts_with("name = ") {
-                name = line.strip_prefix("name = ")
-                    .unwrap_or("Blog")
-                    .trim_matches('"')
-                    .to_string();
-            } else if line.starts_with("domain = ") {
-                domain = line.strip_prefix("domain = ")
-                    .unwrap_or("")
-                    .trim_matches('"')
-                    .to_string();
-            } else if line.starts_with("description = ") {
-                description = line.strip_prefix("description = ")
-                    .unwrap_or("A technical blog")
-                    .trim_matches('"')
-                    .to_string();
-            } else if line.starts_with("default_theme = ") {
-                theme = line.strip_prefix("default_theme = ")
-                    .unwrap_or("system")
-                    .trim_matches('"')
-                    .to_string();
-            }
-        }
-
-        println!("Embedded site config from site.toml: name='{}', domain='{}', theme='{}'", name, domain, theme);
-        (name, domain, description, theme)
+    let content = if config_path.exists() {
+        println!("Embedded site config from site.toml");
+        fs::read_to_string(config_path).expect("Failed to read site.toml")
     } else {
         println!("cargo:warning=site.toml not found, using default embedded config");
-        (
-            String::from("Blog"),
-            String::new(),
-            String::from("A technical blog"),
-            String::from("system")
-        )
+        String::from("[site]\nname = \"Blog\"\ndescription = \"A technical blog\"\n")
     };
 
-    // Generate Rust code with the embedded config
-    let code = format!(
-        r#"pub mod embedded_config {{
-    pub const SITE_NAME: &str = "{}";
-    pub const SITE_DOMAIN: &str = "{}";
-    pub const SITE_DESCRIPTION: &str = "{}";
-    pub const DEFAULT_THEME: &str = "{}";
-}}
-"#,
-        site_name.replace('"', r#"\""#),
-        site_domain.replace('"', r#"\""#),
-        site_description.replace('"', r#"\""#),
-        default_theme.replace('"', r#"\""#)
-    );
-
-    let config_dest = Path::new(out_dir).join("embedded_config.rs");
-    fs::write(&config_dest, code).expect("Failed to write embedded_config.rs");
+    let config_dest = Path::new(out_dir).join("embedded_site.toml");
+    fs::write(&config_dest, content).expect("Failed to write embedded_site.toml");
 }
@@ -1,4 +1,4 @@
-use crate::models::AppState;
+use crate::models::{AppState, PageMeta};
 use askama::Template;
 use axum::{extract::State, http::StatusCode, response::{Html, IntoResponse}};
 use std::sync::Arc;
@@ -9,6 +9,8 @@ struct AboutTemplate {
     content: String,
     site_name: String,
     default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
 }
 
 pub async fn about(State(state): State<Arc<AppState>>) -> impl IntoResponse {
@@ -16,6 +18,13 @@ pub async fn about(State(state): State<Arc<AppState>>) -> impl IntoResponse {
         content: state.about_content.clone(),
         site_name: state.config.site.name.clone(),
         default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta: PageMeta::website(
+            &state.config,
+            "About",
+            &format!("About {}", state.config.site.name),
+            state.config.url_for("/about"),
+        ),
     }).render() {
         Ok(html) => Html(html).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
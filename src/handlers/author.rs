@@ -0,0 +1,57 @@
+use crate::models::{AppState, AuthorConfig, PageMeta, Post};
+use askama::Template;
+use axum::{extract::{Path, State}, http::StatusCode, response::{Html, IntoResponse}};
+use std::sync::Arc;
+
+#[derive(Template)]
+#[template(path = "author.html")]
+struct AuthorTemplate {
+    author: AuthorConfig,
+    /// The author's posts, newest first
+    posts: Vec<Post>,
+    site_name: String,
+    default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
+}
+
+pub async fn author(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(author) = state.config.authors.get(&id).cloned() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut posts: Vec<Post> = state
+        .posts
+        .values()
+        .filter(|post| post.is_listed())
+        .filter(|post| post.authors.iter().any(|author| author.id.as_deref() == Some(id.as_str())))
+        .cloned()
+        .collect();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.date));
+
+    let description = if author.bio.is_empty() { format!("Posts by {}", author.name) } else { author.bio.clone() };
+    let mut meta = PageMeta::website(
+        &state.config,
+        &author.name,
+        &description,
+        state.config.url_for(&format!("/authors/{}", id)),
+    );
+    if let Some(avatar) = &author.avatar {
+        meta.image = state.config.absolute_url(avatar);
+    }
+
+    match (AuthorTemplate {
+        author,
+        posts,
+        site_name: state.config.site.name.clone(),
+        default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta,
+    }).render() {
+        Ok(html) => Html(html).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
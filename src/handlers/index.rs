@@ -1,4 +1,4 @@
-use crate::models::{AppState, Post};
+use crate::models::{AppState, PageMeta, Post};
 use askama::Template;
 use axum::{extract::State, http::StatusCode, response::{Html, IntoResponse}};
 use std::sync::Arc;
@@ -10,17 +10,26 @@ struct IndexTemplate {
     site_name: String,
     site_description: String,
     default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
 }
 
 pub async fn index(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let mut posts: Vec<Post> = state.posts.values().cloned().collect();
-    posts.sort_by(|a, b| b.date.cmp(&a.date));
+    let mut posts: Vec<Post> = state.posts.values().filter(|post| post.is_listed()).cloned().collect();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.date));
 
     match (IndexTemplate {
         posts,
         site_name: state.config.site.name.clone(),
         site_description: state.config.site.description.clone(),
         default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta: PageMeta::website(
+            &state.config,
+            &state.config.site.name,
+            &state.config.site.description,
+            state.config.url_for("/"),
+        ),
     }).render() {
         Ok(html) => Html(html).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
@@ -1,17 +1,23 @@
 mod about;
+mod author;
 mod index;
 mod not_found;
 mod post;
 mod rss;
 mod search;
+mod series;
 mod sitemap;
+mod stats;
 mod static_files;
 
 pub use about::about;
+pub use author::author;
 pub use index::index;
 pub use not_found::not_found;
-pub use post::post;
+pub use post::{post, unlock};
 pub use rss::rss;
 pub use search::search;
+pub use series::series;
 pub use sitemap::{robots, sitemap};
-pub use static_files::serve_static;
+pub use stats::stats;
+pub use static_files::{serve_highlight_css, serve_static};
@@ -1,4 +1,4 @@
-use crate::models::AppState;
+use crate::models::{AppState, PageMeta};
 use askama::Template;
 use axum::{extract::State, http::StatusCode, response::{Html, IntoResponse, Response}};
 use std::sync::Arc;
@@ -9,6 +9,8 @@ struct NotFoundTemplate {
     site_name: String,
     site_description: String,
     default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
 }
 
 pub async fn not_found(State(state): State<Arc<AppState>>) -> Response {
@@ -16,6 +18,8 @@ pub async fn not_found(State(state): State<Arc<AppState>>) -> Response {
         site_name: state.config.site.name.clone(),
         site_description: state.config.site.description.clone(),
         default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta: PageMeta::website(&state.config, "Page not found", &state.config.site.description, None),
     }).render() {
         Ok(html) => (StatusCode::NOT_FOUND, Html(html)).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
@@ -1,29 +1,172 @@
-use crate::models::{AppState, Post};
+use crate::models::{AppState, PageMeta, Post, PostNavigation, Series};
+use crate::utils::{cookie_value, verify_password, UNLOCK_COOKIE};
 use askama::Template;
-use axum::{extract::{Path, State}, http::StatusCode, response::{Html, IntoResponse}};
+use axum::{
+    extract::{Form, Path, State},
+    http::{header, header::HeaderName, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
 use std::sync::Arc;
 
 #[derive(Template)]
 #[template(path = "post.html")]
 struct PostTemplate {
     post: Post,
+    /// The series the post is part of, listed in a box above the content
+    series: Option<Series>,
+    navigation: PostNavigation,
     site_name: String,
     default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
+}
+
+/// Password form shown in place of a protected post
+#[derive(Template)]
+#[template(path = "unlock.html")]
+struct UnlockTemplate {
+    slug: String,
+    title: String,
+    /// Set after a wrong password
+    error: bool,
+    site_name: String,
+    default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
+}
+
+#[derive(Deserialize)]
+pub struct UnlockForm {
+    password: String,
 }
 
 pub async fn post(
     State(state): State<Arc<AppState>>,
     Path(slug): Path<String>,
+    request_headers: HeaderMap,
 ) -> impl IntoResponse {
-    match state.posts.get(&slug).cloned() {
-        Some(post) => match (PostTemplate {
-            post,
-            site_name: state.config.site.name.clone(),
-            default_theme: state.config.site.default_theme.clone(),
-        }).render() {
-            Ok(html) => Html(html).into_response(),
-            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        },
-        None => StatusCode::NOT_FOUND.into_response(),
+    let Some(post) = state.posts.get(&slug).cloned() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    // Same directives as the robots meta tag, for crawlers that only look at headers
+    let mut headers = HeaderMap::new();
+    if let Some(robots) = post.robots_directives().and_then(|robots| HeaderValue::from_str(&robots).ok()) {
+        headers.insert(HeaderName::from_static("x-robots-tag"), robots);
+    }
+    if let Some(password_hash) = &post.password_hash {
+        // Shared caches must never hand an unlocked page to someone else
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-store"));
+        if !is_unlocked(&state, &request_headers, &slug, password_hash) {
+            return (headers, unlock_page(&state, &post, false)).into_response();
+        }
+    }
+
+    let meta = PageMeta::article(&state.config, &post, canonical_url(&state, &post));
+    match (PostTemplate {
+        series: post
+            .series
+            .as_ref()
+            .and_then(|info| state.series.get(&info.slug))
+            .filter(|series| series.position(&post.slug).is_some())
+            .cloned(),
+        navigation: state.navigation.get(&post.slug).cloned().unwrap_or_default(),
+        post,
+        site_name: state.config.site.name.clone(),
+        default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta,
+    }).render() {
+        Ok(html) => (headers, Html(html)).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// Check the password for a protected post, then set its unlock cookie and show the post
+pub async fn unlock(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Form(form): Form<UnlockForm>,
+) -> Response {
+    let Some(post) = state.posts.get(&slug) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let Some(password_hash) = post.password_hash.clone() else {
+        return Redirect::to(&format!("/post/{}", slug)).into_response();
+    };
+
+    // argon2 is deliberately slow, keep it off the async workers
+    let verified = tokio::task::spawn_blocking(move || verify_password(&password_hash, &form.password))
+        .await
+        .unwrap_or(false);
+    if !verified {
+        let mut response = unlock_page(&state, post, true);
+        *response.status_mut() = StatusCode::FORBIDDEN;
+        return response;
+    }
+
+    // Checked at startup, but an overflow here must not panic the handler
+    let Some((max_age, expires)) = state
+        .config
+        .protected
+        .cookie_max_age()
+        .and_then(|max_age| Some((max_age, chrono::Utc::now().timestamp().checked_add(max_age)?)))
+    else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let value = state.unlock_key.sign(&slug, post.password_hash.as_deref().unwrap_or_default(), expires);
+    let secure = if state.config.site.domain.as_deref().is_some_and(|domain| domain.starts_with("https://")) {
+        "; Secure"
+    } else {
+        ""
+    };
+    let cookie = format!(
+        "{}={}; Path=/post/{}; Max-Age={}; HttpOnly; SameSite=Strict{}",
+        UNLOCK_COOKIE, value, slug, max_age, secure
+    );
+
+    match HeaderValue::from_str(&cookie) {
+        // See Other turns the form POST into a plain GET of the post
+        Ok(cookie) => ([(header::SET_COOKIE, cookie)], Redirect::to(&format!("/post/{}", slug))).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// The post's `#+CANONICAL_URL:`, or its URL on this site
+fn canonical_url(state: &AppState, post: &Post) -> Option<String> {
+    post.canonical_url
+        .clone()
+        .or_else(|| state.config.url_for(&format!("/post/{}", post.slug)))
+}
+
+fn is_unlocked(state: &AppState, headers: &HeaderMap, slug: &str, password_hash: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| cookie_value(value, UNLOCK_COOKIE))
+        .any(|value| state.unlock_key.verify(value, slug, password_hash, now))
+}
+
+fn unlock_page(state: &AppState, post: &Post, error: bool) -> Response {
+    match (UnlockTemplate {
+        slug: post.slug.clone(),
+        title: post.title.clone(),
+        error,
+        site_name: state.config.site.name.clone(),
+        default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        // Link previews of a locked post get nothing beyond its title
+        meta: PageMeta::website(
+            &state.config,
+            &post.title,
+            &state.config.site.description,
+            canonical_url(state, post),
+        ),
+    }).render() {
+        Ok(html) => Html(html).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
 }
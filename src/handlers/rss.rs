@@ -4,13 +4,14 @@ use axum::{
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
-use rss::{Channel, ChannelBuilder, ItemBuilder};
+use rss::extension::dublincore::DublinCoreExtension;
+use rss::{CategoryBuilder, Channel, ChannelBuilder, ItemBuilder};
 use std::sync::Arc;
 
 pub async fn rss(State(state): State<Arc<AppState>>) -> Response {
     // Sort posts by date, newest first
-    let mut posts: Vec<_> = state.posts.values().collect();
-    posts.sort_by(|a, b| b.date.cmp(&a.date));
+    let mut posts: Vec<_> = state.posts.values().filter(|post| post.is_listed()).collect();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.date));
 
     // Get the domain or use a default
     let domain = state.config.site.domain.as_deref().unwrap_or("http://localhost:3000");
@@ -19,11 +20,30 @@ pub async fn rss(State(state): State<Arc<AppState>>) -> Response {
     let items: Vec<_> = posts
         .iter()
         .map(|post| {
+            // Series membership as a category whose domain is the series page
+            let categories = post.series.iter().map(|info| {
+                CategoryBuilder::default()
+                    .name(&info.name)
+                    .domain(Some(format!("{}/series/{}", domain, info.slug)))
+                    .build()
+            });
+            // RSS <author> must be an email address, so names go in <dc:creator>
+            let dublin_core = (!post.authors.is_empty()).then(|| DublinCoreExtension {
+                creators: post.authors.iter().map(|author| author.name.clone()).collect(),
+                ..Default::default()
+            });
             ItemBuilder::default()
                 .title(Some(post.title.clone()))
                 .link(Some(format!("{}/post/{}", domain, post.slug)))
-                .description(Some(post.description.clone()))
+                .description(Some(if post.summary_html.is_empty() {
+                    post.description.clone()
+                } else {
+                    post.summary_html.clone()
+                }))
+                .content(Some(post.feed_content.clone()))
                 .pub_date(Some(post.date.format("%a, %d %b %Y 00:00:00 +0000").to_string()))
+                .categories(categories.collect::<Vec<_>>())
+                .dublin_core_ext(dublin_core)
                 .build()
         })
         .collect();
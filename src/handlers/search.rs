@@ -1,4 +1,4 @@
-use crate::models::{AppState, SearchQuery, SearchResult};
+use crate::models::{AppState, PageMeta, SearchQuery, SearchResult};
 use crate::utils::generate_excerpt;
 use askama::Template;
 use axum::{extract::{Query, State}, http::StatusCode, response::{Html, IntoResponse}};
@@ -13,6 +13,8 @@ struct SearchTemplate {
     site_name: String,
     site_description: String,
     default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
 }
 
 pub async fn search(
@@ -37,14 +39,15 @@ pub async fn search(
             state
                 .posts
                 .values()
+                .filter(|post| post.is_listed())
                 .filter(|post| {
                     post.title_lower.contains(&query_lower)
                         || post.content_lower.contains(&query_lower)
                 })
                 .map(|post| {
                     let excerpt = if post.title_lower.contains(&query_lower) {
-                        // If title matches, show beginning of content
-                        generate_excerpt(&post.content, "", 200)
+                        // If title matches, show the summary
+                        generate_excerpt(&post.summary_html, "", 200)
                     } else {
                         // If content matches, show context around match
                         generate_excerpt(&post.content, &query, 200)
@@ -58,17 +61,12 @@ pub async fn search(
         };
 
         // Sort results by date (newest first)
-        results.sort_by(|a, b| b.post.date.cmp(&a.post.date));
+        results.sort_by_key(|result| std::cmp::Reverse(result.post.date));
         results
     };
 
-    let results = match timeout(SEARCH_TIMEOUT, search_future).await {
-        Ok(results) => results,
-        Err(_) => {
-            // Timeout occurred, return empty results
-            Vec::new()
-        }
-    };
+    // Timeout occurred, return empty results
+    let results = timeout(SEARCH_TIMEOUT, search_future).await.unwrap_or_default();
 
     match (SearchTemplate {
         query,
@@ -76,6 +74,13 @@ pub async fn search(
         site_name: state.config.site.name.clone(),
         site_description: state.config.site.description.clone(),
         default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta: PageMeta::website(
+            &state.config,
+            "Search",
+            &state.config.site.description,
+            state.config.url_for("/search"),
+        ),
     }).render() {
         Ok(html) => Html(html).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
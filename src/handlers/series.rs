@@ -0,0 +1,38 @@
+use crate::models::{AppState, PageMeta, Series};
+use askama::Template;
+use axum::{extract::{Path, State}, http::StatusCode, response::{Html, IntoResponse}};
+use std::sync::Arc;
+
+#[derive(Template)]
+#[template(path = "series.html")]
+struct SeriesTemplate {
+    series: Series,
+    site_name: String,
+    default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
+}
+
+pub async fn series(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    match state.series.get(&slug).cloned() {
+        Some(series) => match (SeriesTemplate {
+            meta: PageMeta::website(
+                &state.config,
+                &series.name,
+                &format!("{}, a series in {} parts", series.name, series.parts.len()),
+                state.config.url_for(&format!("/series/{}", slug)),
+            ),
+            series,
+            site_name: state.config.site.name.clone(),
+            default_theme: state.config.site.default_theme.clone(),
+            highlight_css_path: state.highlight_css.path.clone(),
+        }).render() {
+            Ok(html) => Html(html).into_response(),
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        },
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -1,9 +1,11 @@
+use crate::models::AppState;
 use axum::{
-    extract::Path,
+    extract::{Path, State},
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use rust_embed::RustEmbed;
+use std::sync::Arc;
 
 #[derive(RustEmbed)]
 #[folder = "static/"]
@@ -28,3 +30,17 @@ pub async fn serve_static(Path(path): Path<String>) -> Response {
         None => StatusCode::NOT_FOUND.into_response(),
     }
 }
+
+/// Code highlighting stylesheet generated from the configured themes at startup
+pub async fn serve_highlight_css(State(state): State<Arc<AppState>>) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "text/css".parse().unwrap());
+
+    // The path is fingerprinted with the content hash, so it can be cached forever
+    headers.insert(
+        header::CACHE_CONTROL,
+        "public, max-age=31536000, immutable".parse().unwrap(),
+    );
+
+    (headers, state.highlight_css.content.clone()).into_response()
+}
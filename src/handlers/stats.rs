@@ -0,0 +1,32 @@
+use crate::models::{AppState, PageMeta, SiteStats};
+use askama::Template;
+use axum::{extract::State, http::StatusCode, response::{Html, IntoResponse}};
+use std::sync::Arc;
+
+#[derive(Template)]
+#[template(path = "stats.html")]
+struct StatsTemplate {
+    stats: SiteStats,
+    site_name: String,
+    default_theme: String,
+    highlight_css_path: String,
+    meta: PageMeta,
+}
+
+pub async fn stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match (StatsTemplate {
+        stats: SiteStats::from_posts(state.posts.values().filter(|post| post.is_listed())),
+        site_name: state.config.site.name.clone(),
+        default_theme: state.config.site.default_theme.clone(),
+        highlight_css_path: state.highlight_css.path.clone(),
+        meta: PageMeta::website(
+            &state.config,
+            "Stats",
+            &format!("Writing statistics for {}", state.config.site.name),
+            state.config.url_for("/stats"),
+        ),
+    }).render() {
+        Ok(html) => Html(html).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
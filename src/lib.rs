@@ -3,15 +3,16 @@ mod utils;
 mod parsing;
 mod handlers;
 mod server;
+pub mod sitemap;
 
 // Re-export models for public API
-pub use models::{Post, AppState, SearchQuery, SearchResult, SiteConfig};
+pub use models::{Post, AppState, Author, AuthorConfig, Series, SeriesInfo, Visibility, FingerprintedAsset, SearchQuery, SearchResult, SiteConfig};
 
 // Re-export parsing functions
-pub use parsing::parse_posts;
+pub use parsing::{build_navigation, collect_series, highlight_css, parse_about, parse_posts};
 
 // Re-export handlers
-pub use handlers::{index, post, search, serve_static};
+pub use handlers::{author, index, post, unlock, rss, search, series, serve_highlight_css, serve_static, stats};
 
 // Re-export server
 pub use server::run;
@@ -1,8 +1,39 @@
-use super::{Post, SiteConfig};
+use crate::utils::UnlockKey;
+use super::{FingerprintedAsset, Post, PostNavigation, Series, SiteConfig};
 use std::collections::HashMap;
 
 pub struct AppState {
     pub posts: HashMap<String, Post>,
+    /// Series keyed by slug, see `collect_series`
+    pub series: HashMap<String, Series>,
+    /// Previous/next and related posts keyed by post slug, see `build_navigation`
+    pub navigation: HashMap<String, PostNavigation>,
     pub about_content: String,
     pub config: SiteConfig,
+    pub highlight_css: FingerprintedAsset,
+    /// Signs the cookies that unlock password-protected posts
+    pub(crate) unlock_key: UnlockKey,
+}
+
+#[cfg(feature = "test-support")]
+impl AppState {
+    /// State for integration tests, with a fixed key so unlock cookies survive a rebuilt server
+    pub fn for_tests(
+        posts: HashMap<String, Post>,
+        series: HashMap<String, Series>,
+        navigation: HashMap<String, PostNavigation>,
+        about_content: String,
+        config: SiteConfig,
+        highlight_css: FingerprintedAsset,
+    ) -> Self {
+        Self {
+            posts,
+            series,
+            navigation,
+            about_content,
+            config,
+            highlight_css,
+            unlock_key: UnlockKey::new(Some("test secret")),
+        }
+    }
 }
@@ -0,0 +1,22 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// A static file generated at startup, served under a content-hashed path
+/// so it can be cached forever and still change when the content does
+#[derive(Clone, Debug)]
+pub struct FingerprintedAsset {
+    pub path: String,
+    pub content: String,
+}
+
+impl FingerprintedAsset {
+    /// `new("highlight", "css", ..)` is served at `/static/highlight.<hash>.css`
+    pub fn new(name: &str, extension: &str, content: String) -> Self {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+
+        Self {
+            path: format!("/static/{}.{:016x}.{}", name, hasher.finish(), extension),
+            content,
+        }
+    }
+}
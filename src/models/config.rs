@@ -1,15 +1,35 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
-// Include the embedded config generated at build time
-include!(concat!(env!("OUT_DIR"), "/embedded_config.rs"));
+/// site.toml as it was at build time, copied by build.rs
+const EMBEDDED_SITE_TOML: &str = include_str!(concat!(env!("OUT_DIR"), "/embedded_site.toml"));
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct SiteConfig {
     pub site: SiteMetadata,
     #[serde(default)]
     pub server: ServerConfig,
+    #[serde(default)]
+    pub highlight: HighlightConfig,
+    /// Special blocks rendered as callouts, keyed by block name (`#+begin_<name>`)
+    #[serde(default = "default_callouts")]
+    pub callouts: HashMap<String, CalloutStyle>,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub citations: CitationConfig,
+    #[serde(default)]
+    pub typography: TypographyConfig,
+    #[serde(default)]
+    pub raw_html: RawHtmlConfig,
+    /// People who write posts, keyed by the id used in `#+AUTHOR:` and `/authors/{id}`
+    #[serde(default)]
+    pub authors: HashMap<String, AuthorConfig>,
+    #[serde(default)]
+    pub protected: ProtectedConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -19,6 +39,9 @@ pub struct SiteMetadata {
     pub description: String,
     #[serde(default = "default_theme")]
     pub default_theme: String,
+    /// Link preview image for pages and posts without an `#+IMAGE:`
+    #[serde(default)]
+    pub image: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -27,6 +50,181 @@ pub struct ServerConfig {
     pub bind_address: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct HighlightConfig {
+    /// Theme for `[data-theme="light"]`: a `themes/<name>.tmTheme` file or a syntect bundled theme
+    #[serde(default = "default_light_theme")]
+    pub light_theme: String,
+    /// Theme for `[data-theme="dark"]`
+    #[serde(default = "default_dark_theme")]
+    pub dark_theme: String,
+    /// Theme for inline-styled code in feeds and other off-site HTML, defaults to `light_theme`
+    #[serde(default)]
+    pub feed_theme: Option<String>,
+    /// Source block language aliases, e.g. `elisp = "lisp"`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ExportConfig {
+    /// Site-wide `#+OPTIONS:` defaults, e.g. "toc:2 num:nil ^:{} H:6"
+    #[serde(default)]
+    pub options: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct CitationConfig {
+    /// How `[cite:@key]` is rendered and the references list ordered
+    #[serde(default)]
+    pub style: CitationStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CitationStyle {
+    /// `[1]`, references listed in order of first citation
+    #[default]
+    Numeric,
+    /// `(Knuth, 1984)`, references sorted by author and year
+    AuthorYear,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TypographyConfig {
+    /// Curly quotes, dashes, ellipses and non-breaking spaces in post text
+    #[serde(default)]
+    pub enabled: bool,
+    /// Words at least this long get soft hyphens (English patterns), 0 disables hyphenation
+    #[serde(default = "default_hyphenate_min_length")]
+    pub hyphenate_min_length: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RawHtmlConfig {
+    /// What happens to `#+begin_export html` blocks, `@@html:...@@` snippets and script links
+    #[serde(default)]
+    pub policy: RawHtmlPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RawHtmlPolicy {
+    /// Written as-is, for sites where every author is trusted
+    Allow,
+    /// Anything outside the allowlist is removed and reported at startup
+    #[default]
+    Sanitize,
+    /// Anything outside the allowlist stops the site from starting
+    Reject,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtectedConfig {
+    /// Key for the cookies that unlock `#+PASSWORD_HASH:` posts; random per start when unset,
+    /// which locks every post again on restart. Prefer the `COOKIE_SECRET` environment variable
+    #[serde(default)]
+    pub cookie_secret: Option<String>,
+    /// How long an unlocked post stays unlocked
+    #[serde(default = "default_cookie_days")]
+    pub cookie_days: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorConfig {
+    pub name: String,
+    #[serde(default)]
+    pub bio: String,
+    /// Image URL, e.g. `/static/authors/sean.jpg`
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// Profile links shown on the author page, in order
+    #[serde(default)]
+    pub links: Vec<AuthorLink>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorLink {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalloutStyle {
+    /// Title shown when the block has no `#+begin_<name> Title` parameters
+    pub title: String,
+    /// Built-in icon: note, tip, important, warning or danger (defaults to the block name)
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl HighlightConfig {
+    pub fn feed_theme(&self) -> &str {
+        self.feed_theme.as_deref().unwrap_or(&self.light_theme)
+    }
+}
+
+impl SiteConfig {
+    /// Absolute URL of a path on this site, `None` when no domain is configured
+    pub fn url_for(&self, path: &str) -> Option<String> {
+        let domain = self.site.domain.as_deref()?.trim_end_matches('/');
+        Some(format!("{}{}", domain, path))
+    }
+
+    /// A site path such as `/static/card.png` made absolute, other URLs as they are
+    pub fn absolute_url(&self, url: &str) -> Option<String> {
+        if url.starts_with('/') {
+            self.url_for(url)
+        } else {
+            Some(url.to_string())
+        }
+    }
+
+    /// The id of a configured author, matched by id or by name, ignoring case
+    pub fn author_id(&self, author: &str) -> Option<&str> {
+        self.authors
+            .iter()
+            .find(|(id, config)| id.eq_ignore_ascii_case(author) || config.name.eq_ignore_ascii_case(author))
+            .map(|(id, _)| id.as_str())
+    }
+}
+
+impl ProtectedConfig {
+    /// `cookie_days` in seconds, `None` when that does not fit in an i64
+    pub fn cookie_max_age(&self) -> Option<i64> {
+        self.cookie_days.checked_mul(24 * 60 * 60)
+    }
+}
+
+impl Default for ProtectedConfig {
+    fn default() -> Self {
+        Self {
+            cookie_secret: None,
+            cookie_days: default_cookie_days(),
+        }
+    }
+}
+
+impl Default for TypographyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hyphenate_min_length: default_hyphenate_min_length(),
+        }
+    }
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            light_theme: default_light_theme(),
+            dark_theme: default_dark_theme(),
+            feed_theme: None,
+            aliases: HashMap::new(),
+        }
+    }
+}
+
 fn default_bind_address() -> String {
     "127.0.0.1:3000".to_string()
 }
@@ -35,6 +233,43 @@ fn default_theme() -> String {
     "system".to_string()
 }
 
+fn default_callouts() -> HashMap<String, CalloutStyle> {
+    [
+        ("note", "Note"),
+        ("tip", "Tip"),
+        ("important", "Important"),
+        ("warning", "Warning"),
+        ("danger", "Danger"),
+    ]
+    .into_iter()
+    .map(|(name, title)| {
+        (
+            name.to_string(),
+            CalloutStyle {
+                title: title.to_string(),
+                icon: None,
+            },
+        )
+    })
+    .collect()
+}
+
+fn default_cookie_days() -> i64 {
+    30
+}
+
+fn default_hyphenate_min_length() -> usize {
+    10
+}
+
+fn default_light_theme() -> String {
+    "ef-maris-light".to_string()
+}
+
+fn default_dark_theme() -> String {
+    "ef-maris-dark".to_string()
+}
+
 impl SiteConfig {
     /// Load configuration from a TOML file
     pub fn from_file(path: &str) -> Result<Self> {
@@ -47,27 +282,14 @@ impl SiteConfig {
         Ok(config)
     }
 
-    /// Load configuration from file or use defaults with environment variable overrides
+    /// The site.toml embedded at build time, for deploys that ship only the binary
+    pub fn embedded() -> Result<Self> {
+        toml::from_str(EMBEDDED_SITE_TOML).context("Failed to parse embedded build-time site.toml")
+    }
+
+    /// Load configuration from file or the embedded build-time copy, with environment variable overrides
     pub fn load() -> Result<Self> {
-        // Try to load from site.toml, fall back to embedded build-time config
-        let config = Self::from_file("site.toml").unwrap_or_else(|_| {
-            eprintln!("Warning: site.toml not found, using embedded build-time configuration");
-            SiteConfig {
-                site: SiteMetadata {
-                    name: embedded_config::SITE_NAME.to_string(),
-                    domain: if embedded_config::SITE_DOMAIN.is_empty() {
-                        None
-                    } else {
-                        Some(embedded_config::SITE_DOMAIN.to_string())
-                    },
-                    description: embedded_config::SITE_DESCRIPTION.to_string(),
-                    default_theme: embedded_config::DEFAULT_THEME.to_string(),
-                },
-                server: ServerConfig {
-                    bind_address: default_bind_address(),
-                },
-            }
-        });
+        let config = Self::load_from(Path::new("site.toml"), EMBEDDED_SITE_TOML)?;
 
         // Override with environment variables if present
         let config = Self::apply_env_overrides(config);
@@ -75,6 +297,23 @@ impl SiteConfig {
         Ok(config)
     }
 
+    /// The config file at `path`, or `embedded` when it is missing or does not parse
+    fn load_from(path: &Path, embedded: &str) -> Result<Self> {
+        let embedded = || toml::from_str(embedded).context("Failed to parse embedded build-time site.toml");
+        if !path.exists() {
+            eprintln!("Warning: {} not found, using embedded build-time configuration", path.display());
+            return embedded();
+        }
+
+        match Self::from_file(&path.to_string_lossy()) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                eprintln!("Warning: {:#}, using embedded build-time configuration", e);
+                embedded()
+            }
+        }
+    }
+
     /// Apply environment variable overrides to configuration
     fn apply_env_overrides(mut config: Self) -> Self {
         if let Ok(description) = std::env::var("SITE_DESCRIPTION") {
@@ -89,6 +328,10 @@ impl SiteConfig {
             config.site.name = name;
         }
 
+        if let Ok(secret) = std::env::var("COOKIE_SECRET") {
+            config.protected.cookie_secret = Some(secret);
+        }
+
         if let Ok(addr) = std::env::var("BIND_ADDRESS") {
             config.server.bind_address = addr;
         }
@@ -96,3 +339,66 @@ impl SiteConfig {
         config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const EMBEDDED: &str = r#"
+        [site]
+        name = "Embedded"
+        domain = "https://example.com"
+        description = "Built in"
+
+        [highlight.aliases]
+        elisp = "lisp"
+
+        [authors.sean]
+        name = "Sean"
+    "#;
+
+    fn load_file(name: &str, content: Option<&str>) -> SiteConfig {
+        let path = std::env::temp_dir().join(format!("blog-engine-{}-site.toml", name));
+        if let Some(content) = content {
+            fs::write(&path, content).unwrap();
+        }
+        let config = SiteConfig::load_from(&path, EMBEDDED);
+        fs::remove_file(&path).ok();
+        config.expect("config should load")
+    }
+
+    #[test]
+    fn test_missing_config_uses_every_embedded_section() {
+        let config = load_file("missing", None);
+
+        assert_eq!(config.site.name, "Embedded");
+        assert_eq!(config.site.domain.as_deref(), Some("https://example.com"));
+        assert_eq!(config.highlight.aliases, HashMap::from([("elisp".to_string(), "lisp".to_string())]));
+        assert_eq!(config.authors["sean"].name, "Sean");
+    }
+
+    #[test]
+    fn test_config_file_replaces_embedded() {
+        let config = load_file("valid", Some("[site]\nname = \"File\"\ndescription = \"On disk\"\n"));
+
+        assert_eq!(config.site.name, "File");
+        assert!(config.highlight.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_cookie_max_age_overflow() {
+        let protected = |cookie_days| ProtectedConfig { cookie_secret: None, cookie_days };
+
+        assert_eq!(protected(30).cookie_max_age(), Some(30 * 86400));
+        assert_eq!(protected(i64::MAX / 86400 + 1).cookie_max_age(), None);
+    }
+
+    #[test]
+    fn test_malformed_config_falls_back_to_embedded() {
+        let config = load_file("malformed", Some("[site\nname = "));
+
+        assert_eq!(config.site.name, "Embedded");
+        assert_eq!(config.highlight.aliases["elisp"], "lisp");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::models::{Post, SiteConfig};
+use serde_json::{json, Value};
+
+/// Open Graph, Twitter card and JSON-LD metadata for a page's `<head>`
+#[derive(Clone, Debug, Default)]
+pub struct PageMeta {
+    /// Page title without the site name
+    pub title: String,
+    pub description: String,
+    /// Canonical URL, also used for `og:url`
+    pub url: Option<String>,
+    /// Absolute URL of the link preview image
+    pub image: Option<String>,
+    /// Set for posts, which are `og:type` article rather than website
+    pub article: Option<ArticleMeta>,
+    /// schema.org `WebSite` or `BlogPosting`, safe to place inside `<script>`
+    pub json_ld: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArticleMeta {
+    pub published: String,
+    /// Last git change, or the publication date for posts never updated
+    pub modified: String,
+    /// Author page URLs, or names for authors without a page
+    pub authors: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl PageMeta {
+    /// Metadata for a page other than a post, described in JSON-LD as the site itself
+    pub fn website(config: &SiteConfig, title: &str, description: &str, url: Option<String>) -> Self {
+        let home = config.url_for("/");
+        let mut website = json!({
+            "@context": "https://schema.org",
+            "@type": "WebSite",
+            "name": config.site.name,
+            "description": config.site.description,
+        });
+        if let Some(home) = home {
+            website["url"] = json!(home);
+        }
+
+        Self {
+            title: title.to_string(),
+            description: description.to_string(),
+            url,
+            image: config.site.image.as_deref().and_then(|image| config.absolute_url(image)),
+            article: None,
+            json_ld: script_json(&website),
+        }
+    }
+
+    /// Metadata for a post, described in JSON-LD as a `BlogPosting`
+    /// Protected posts get the site description, theirs is for readers who unlocked them
+    pub fn article(config: &SiteConfig, post: &Post, url: Option<String>) -> Self {
+        let description = if post.is_protected() { &config.site.description } else { &post.description };
+        let image = post
+            .image
+            .as_deref()
+            .or(config.site.image.as_deref())
+            .and_then(|image| config.absolute_url(image));
+        let published = post.date.format("%Y-%m-%d").to_string();
+        let modified = if post.updated.is_empty() { published.clone() } else { post.updated.clone() };
+        let author_urls: Vec<Option<String>> = post
+            .authors
+            .iter()
+            .map(|author| author.id.as_ref().and_then(|id| config.url_for(&format!("/authors/{}", id))))
+            .collect();
+
+        let mut posting = json!({
+            "@context": "https://schema.org",
+            "@type": "BlogPosting",
+            "headline": post.title,
+            "description": description,
+            "datePublished": published,
+            "dateModified": modified,
+            "author": post.authors.iter().zip(&author_urls).map(|(author, url)| {
+                let mut person = json!({ "@type": "Person", "name": author.name });
+                if let Some(url) = url {
+                    person["url"] = json!(url);
+                }
+                person
+            }).collect::<Vec<_>>(),
+            "publisher": { "@type": "Organization", "name": config.site.name },
+        });
+        if let Some(url) = &url {
+            posting["url"] = json!(url);
+            posting["mainEntityOfPage"] = json!(url);
+        }
+        if let Some(image) = &image {
+            posting["image"] = json!(image);
+        }
+        if !post.tags.is_empty() {
+            posting["keywords"] = json!(post.tags.join(", "));
+        }
+        if let Some(series) = &post.series {
+            let mut part_of = json!({ "@type": "CreativeWorkSeries", "name": series.name });
+            if let Some(url) = config.url_for(&format!("/series/{}", series.slug)) {
+                part_of["url"] = json!(url);
+            }
+            posting["isPartOf"] = part_of;
+            if let Some(part) = series.part {
+                posting["position"] = json!(part);
+            }
+        }
+
+        Self {
+            title: post.title.clone(),
+            description: description.clone(),
+            url,
+            image,
+            article: Some(ArticleMeta {
+                published,
+                modified,
+                authors: post
+                    .authors
+                    .iter()
+                    .zip(author_urls)
+                    .map(|(author, url)| url.unwrap_or_else(|| author.name.clone()))
+                    .collect(),
+                tags: post.tags.clone(),
+            }),
+            json_ld: script_json(&posting),
+        }
+    }
+
+    /// `summary_large_image` when there is an image to show, otherwise `summary`
+    pub fn twitter_card(&self) -> &'static str {
+        if self.image.is_some() { "summary_large_image" } else { "summary" }
+    }
+}
+
+/// JSON that cannot close its `<script>` element or be read as HTML
+fn script_json(value: &Value) -> String {
+    value
+        .to_string()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Author, SeriesInfo};
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn config() -> SiteConfig {
+        toml::from_str(
+            r#"
+            [site]
+            name = "Blog"
+            domain = "https://example.com/"
+            description = "A blog"
+            image = "/static/card.png"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_article_meta_from_post() {
+        let post = Post {
+            title: "Hello </script>".to_string(),
+            description: "First post".to_string(),
+            date: NaiveDate::from_ymd_opt(2025, 1, 20).unwrap(),
+            authors: vec![
+                Author { id: Some("sean".to_string()), name: "Sean".to_string() },
+                Author { id: None, name: "Guest".to_string() },
+            ],
+            tags: vec!["rust".to_string(), "nix".to_string()],
+            image: Some("https://cdn.example.com/hero.png".to_string()),
+            series: Some(SeriesInfo {
+                name: "Getting Started".to_string(),
+                slug: "getting-started".to_string(),
+                part: Some(2),
+            }),
+            ..Default::default()
+        };
+        let meta = PageMeta::article(&config(), &post, Some("https://example.com/post/hello".to_string()));
+
+        assert_eq!(meta.image.as_deref(), Some("https://cdn.example.com/hero.png"));
+        assert_eq!(meta.twitter_card(), "summary_large_image");
+        let article = meta.article.unwrap();
+        assert_eq!(article.modified, "2025-01-20");
+        assert_eq!(article.authors, vec!["https://example.com/authors/sean", "Guest"]);
+
+        assert!(!meta.json_ld.contains("</script>"));
+        let json: Value = serde_json::from_str(&meta.json_ld).unwrap();
+        assert_eq!(json["@type"], "BlogPosting");
+        assert_eq!(json["headline"], "Hello </script>");
+        assert_eq!(json["keywords"], "rust, nix");
+        assert_eq!(json["author"][0]["url"], "https://example.com/authors/sean");
+        assert_eq!(json["author"][1], json!({ "@type": "Person", "name": "Guest" }));
+        assert_eq!(
+            json["isPartOf"],
+            json!({
+                "@type": "CreativeWorkSeries",
+                "name": "Getting Started",
+                "url": "https://example.com/series/getting-started",
+            })
+        );
+        assert_eq!(json["position"], 2);
+    }
+
+    #[test]
+    fn test_protected_post_meta_hides_description() {
+        let post = Post {
+            title: "Secret".to_string(),
+            description: "What the password hides".to_string(),
+            password_hash: Some("$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$aGFzaA".to_string()),
+            ..Default::default()
+        };
+        let meta = PageMeta::article(&config(), &post, None);
+
+        assert_eq!(meta.description, "A blog");
+        assert!(!meta.json_ld.contains("What the password hides"));
+    }
+
+    #[test]
+    fn test_website_meta_uses_site_image() {
+        let meta = PageMeta::website(&config(), "Stats", "Writing statistics", None);
+
+        assert_eq!(meta.image.as_deref(), Some("https://example.com/static/card.png"));
+        assert!(meta.article.is_none());
+        let json: Value = serde_json::from_str(&meta.json_ld).unwrap();
+        assert_eq!(json["@type"], "WebSite");
+        assert_eq!(json["url"], "https://example.com/");
+    }
+}
@@ -2,8 +2,18 @@ mod post;
 mod app_state;
 mod search;
 mod config;
+mod asset;
+mod series;
+mod navigation;
+mod stats;
+mod meta;
 
-pub use post::{Post, TocItem};
+pub use post::{Author, Post, PostStats, SeriesInfo, TocItem, Visibility};
 pub use app_state::AppState;
 pub use search::{SearchQuery, SearchResult};
-pub use config::SiteConfig;
+pub use config::{AuthorConfig, CalloutStyle, CitationStyle, HighlightConfig, RawHtmlPolicy, SiteConfig};
+pub use asset::FingerprintedAsset;
+pub use series::{Series, SeriesPart};
+pub use navigation::{PostLink, PostNavigation};
+pub use stats::SiteStats;
+pub use meta::PageMeta;
@@ -0,0 +1,17 @@
+/// A link to another post, as shown in navigation
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostLink {
+    pub slug: String,
+    pub title: String,
+}
+
+/// Links shown at the end of a post, computed once at startup
+#[derive(Clone, Debug, Default)]
+pub struct PostNavigation {
+    /// The next older post
+    pub previous: Option<PostLink>,
+    /// The next newer post
+    pub next: Option<PostLink>,
+    /// Most similar posts first
+    pub related: Vec<PostLink>,
+}
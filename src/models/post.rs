@@ -6,9 +6,62 @@ pub struct TocItem {
     pub id: String,
     pub text: String,
     pub level: usize,
+    /// Section number such as "2.1" when the post uses `num:` in `#+OPTIONS:`
+    pub number: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+/// The series a post belongs to, from `#+SERIES:` and `#+SERIES_PART:`
+#[derive(Clone, Debug, Default)]
+pub struct SeriesInfo {
+    pub name: String,
+    /// URL segment for `/series/{slug}`
+    pub slug: String,
+    /// Explicit order within the series, posts without one follow by date
+    pub part: Option<usize>,
+}
+
+/// Where a post is listed, from `#+VISIBILITY:`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    /// Served at its URL with `noindex`, but left out of listings, feeds, search and the sitemap
+    Unlisted,
+}
+
+/// A byline entry from `#+AUTHOR:`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Author {
+    /// Key of `[authors.<id>]` in site.toml, `None` for a name with no author page
+    pub id: Option<String>,
+    pub name: String,
+}
+
+/// Reading statistics for a post, computed by `parse_posts`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostStats {
+    /// Words of prose, code and diagrams excluded
+    pub word_count: usize,
+    /// At `WORDS_PER_MINUTE`, rounded up and at least one minute
+    pub reading_minutes: usize,
+    /// `#+begin_src` blocks, including diagrams
+    pub code_blocks: usize,
+}
+
+/// Reading speed used for `PostStats::reading_minutes`
+const WORDS_PER_MINUTE: usize = 200;
+
+impl PostStats {
+    pub fn new(word_count: usize, code_blocks: usize) -> Self {
+        Self {
+            word_count,
+            reading_minutes: word_count.div_ceil(WORDS_PER_MINUTE).max(1),
+            code_blocks,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct Post {
     pub slug: String,
     pub title: String,
@@ -16,7 +69,75 @@ pub struct Post {
     pub updated: String,
     pub description: String,
     pub content: String,
+    /// From `#+begin_summary`, the text before `#+MORE`, or the first paragraph, for listings and feeds
+    pub summary_html: String,
+    /// `content` with inline-styled code, for feeds and other off-site readers
+    pub feed_content: String,
     pub title_lower: String,
     pub content_lower: String,
     pub toc: Vec<TocItem>,
+    pub series: Option<SeriesInfo>,
+    pub authors: Vec<Author>,
+    /// From `#+FILETAGS:`, lowercased
+    pub tags: Vec<String>,
+    pub stats: PostStats,
+    pub visibility: Visibility,
+    /// argon2 PHC string from `#+PASSWORD_HASH:`, readers must unlock the post to see it
+    pub password_hash: Option<String>,
+    /// From `#+CANONICAL_URL:`, for posts first published elsewhere
+    pub canonical_url: Option<String>,
+    /// Hero image for link previews from `#+IMAGE:`, a site path or an absolute URL
+    pub image: Option<String>,
+    /// Directives from `#+ROBOTS:`, lowercased, e.g. `["noindex", "nofollow"]`
+    pub robots: Vec<String>,
+}
+
+impl Post {
+    /// Whether the post appears in the index, feeds, search and other listings
+    pub fn is_listed(&self) -> bool {
+        self.visibility == Visibility::Public && !self.is_protected()
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    /// Robots meta and `X-Robots-Tag` value: `#+ROBOTS:`, plus `noindex` for posts kept out of listings
+    pub fn robots_directives(&self) -> Option<String> {
+        let mut directives = self.robots.clone();
+        let excludes_index = directives.iter().any(|directive| directive == "noindex" || directive == "none");
+        if !self.is_listed() && !excludes_index {
+            directives.insert(0, "noindex".to_string());
+        }
+        (!directives.is_empty()).then(|| directives.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn post(robots: &[&str], visibility: Visibility) -> Post {
+        Post {
+            robots: robots.iter().map(|directive| directive.to_string()).collect(),
+            visibility,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_robots_directives() {
+        assert_eq!(post(&[], Visibility::Public).robots_directives(), None);
+        assert_eq!(
+            post(&["noindex", "nofollow"], Visibility::Public).robots_directives().as_deref(),
+            Some("noindex, nofollow")
+        );
+        // Posts kept out of listings are noindex whatever #+ROBOTS: says
+        assert_eq!(
+            post(&["nofollow"], Visibility::Unlisted).robots_directives().as_deref(),
+            Some("noindex, nofollow")
+        );
+        assert_eq!(post(&["none"], Visibility::Unlisted).robots_directives().as_deref(), Some("none"));
+    }
 }
@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+
+/// Posts sharing a `#+SERIES:` name, in reading order
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: String,
+    pub slug: String,
+    pub parts: Vec<SeriesPart>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SeriesPart {
+    pub slug: String,
+    pub title: String,
+    pub date: NaiveDate,
+    pub description: String,
+}
+
+impl Series {
+    /// 1-based position of a post in the series
+    pub fn position(&self, post_slug: &str) -> Option<usize> {
+        self.parts.iter().position(|part| part.slug == post_slug).map(|index| index + 1)
+    }
+}
@@ -0,0 +1,117 @@
+use super::Post;
+use chrono::Datelike;
+use std::collections::{BTreeMap, HashMap};
+
+/// How many tags the stats page lists
+const TOP_TAGS: usize = 10;
+
+/// Writing output across all posts, for the `/stats` page
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiteStats {
+    pub posts: usize,
+    pub words: usize,
+    pub code_blocks: usize,
+    /// Newest year first
+    pub years: Vec<YearStats>,
+    /// Most used first, as (tag, post count)
+    pub top_tags: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct YearStats {
+    pub year: i32,
+    pub posts: usize,
+    pub words: usize,
+}
+
+impl SiteStats {
+    pub fn from_posts<'a>(posts: impl IntoIterator<Item = &'a Post>) -> Self {
+        let mut stats = SiteStats {
+            posts: 0,
+            words: 0,
+            code_blocks: 0,
+            years: Vec::new(),
+            top_tags: Vec::new(),
+        };
+        let mut years: BTreeMap<i32, YearStats> = BTreeMap::new();
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+
+        for post in posts {
+            stats.posts += 1;
+            stats.words += post.stats.word_count;
+            stats.code_blocks += post.stats.code_blocks;
+
+            let year = post.date.year();
+            let year_stats = years.entry(year).or_insert(YearStats { year, posts: 0, words: 0 });
+            year_stats.posts += 1;
+            year_stats.words += post.stats.word_count;
+
+            for tag in &post.tags {
+                *tags.entry(tag.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        stats.years = years.into_values().rev().collect();
+        let mut top_tags: Vec<(String, usize)> = tags.into_iter().map(|(tag, count)| (tag.to_string(), count)).collect();
+        top_tags.sort_by(|(a_tag, a_count), (b_tag, b_count)| b_count.cmp(a_count).then_with(|| a_tag.cmp(b_tag)));
+        top_tags.truncate(TOP_TAGS);
+        stats.top_tags = top_tags;
+        stats
+    }
+
+    /// Average words per post, 0 without posts
+    pub fn average_words(&self) -> usize {
+        self.words.checked_div(self.posts).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PostStats;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn post(year: i32, words: usize, tags: &[&str]) -> Post {
+        Post {
+            date: NaiveDate::from_ymd_opt(year, 6, 1).unwrap(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            stats: PostStats::new(words, 1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_site_stats_by_year_and_tag() {
+        let posts = [
+            post(2024, 300, &["rust"]),
+            post(2025, 1000, &["nix", "rust"]),
+            post(2025, 500, &["nix"]),
+            post(2025, 200, &["emacs"]),
+        ];
+        let stats = SiteStats::from_posts(&posts);
+
+        assert_eq!(stats.posts, 4);
+        assert_eq!(stats.words, 2000);
+        assert_eq!(stats.code_blocks, 4);
+        assert_eq!(stats.average_words(), 500);
+        assert_eq!(
+            stats.years,
+            vec![
+                YearStats { year: 2025, posts: 3, words: 1700 },
+                YearStats { year: 2024, posts: 1, words: 300 },
+            ]
+        );
+        assert_eq!(
+            stats.top_tags,
+            vec![("nix".to_string(), 2), ("rust".to_string(), 2), ("emacs".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_reading_minutes_round_up() {
+        assert_eq!(PostStats::new(0, 0).reading_minutes, 1);
+        assert_eq!(PostStats::new(200, 0).reading_minutes, 1);
+        assert_eq!(PostStats::new(201, 0).reading_minutes, 2);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::utils::slugify;
+use orgize::elements::Title;
+use orgize::Org;
+use std::collections::{HashMap, HashSet};
+
+/// Heading ids for one document, assigned in document order
+/// A `:CUSTOM_ID:` property is used as-is; otherwise the title is slugified and
+/// repeats get `-2`, `-3`... so an id only changes when its own heading does
+#[derive(Debug, Default)]
+pub struct HeadingAnchors {
+    ids: Vec<String>,
+    used: HashSet<String>,
+    // Custom ids claimed up front, so an earlier heading's slug cannot take them
+    reserved: HashSet<String>,
+    // Heading text by id, and id by heading text, for internal links
+    titles: HashMap<String, String>,
+    by_title: HashMap<String, String>,
+}
+
+impl HeadingAnchors {
+    /// Assign ids to every heading up front, so links can point forward in the document
+    pub fn for_org(org: &Org) -> Self {
+        let mut anchors = Self {
+            reserved: org
+                .headlines()
+                .filter_map(|headline| custom_id(headline.title(org)))
+                .map(str::to_string)
+                .collect(),
+            ..Self::default()
+        };
+        for headline in org.headlines() {
+            anchors.assign(headline.title(org));
+        }
+        anchors
+    }
+
+    /// Id of the `index`-th heading, assigned now if the document was not scanned up front
+    pub fn id_for(&mut self, index: usize, title: &Title) -> String {
+        match self.ids.get(index) {
+            Some(id) => id.clone(),
+            None => self.assign(title),
+        }
+    }
+
+    /// Claim an id for generated content such as the references section, deduped against the headings
+    pub fn unique_id(&mut self, base: &str) -> String {
+        let mut id = base.to_string();
+        let mut repeat = 2;
+        while self.used.contains(&id) || self.reserved.contains(&id) {
+            id = format!("{}-{}", base, repeat);
+            repeat += 1;
+        }
+        self.used.insert(id.clone());
+        id
+    }
+
+    fn assign(&mut self, title: &Title) -> String {
+        let text = clean_org_links(title.raw.trim());
+        let id = match custom_id(title) {
+            Some(id) => {
+                self.used.insert(id.to_string());
+                id.to_string()
+            }
+            None => match slugify(&text) {
+                slug if slug.is_empty() => self.unique_id("section"),
+                slug => self.unique_id(&slug),
+            },
+        };
+
+        self.titles.entry(id.clone()).or_insert_with(|| text.clone());
+        self.by_title.entry(text).or_insert_with(|| id.clone());
+        self.ids.push(id.clone());
+        id
+    }
+
+    /// Resolve an internal link path, `#custom-id` or `*Heading text`, to (id, heading text)
+    pub fn resolve(&self, path: &str) -> Option<(&str, &str)> {
+        let id = match (path.strip_prefix('#'), path.strip_prefix('*')) {
+            (Some(id), _) => id,
+            (_, Some(heading)) => self.by_title.get(heading.trim())?.as_str(),
+            _ => return None,
+        };
+        self.titles
+            .get_key_value(id)
+            .map(|(id, text)| (id.as_str(), text.as_str()))
+    }
+}
+
+fn custom_id<'a>(title: &'a Title) -> Option<&'a str> {
+    title
+        .properties
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("CUSTOM_ID"))
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+/// Clean org-mode link syntax from text
+/// Converts [[URL][link text]] to "link text"
+/// Converts [[URL]] to "URL"
+pub fn clean_org_links(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '[' && chars.peek() == Some(&'[') {
+            chars.next(); // consume second '['
+
+            // Extract the URL part
+            let mut url = String::new();
+            while let Some(c) = chars.next() {
+                if c == ']' && chars.peek() == Some(&']') {
+                    chars.next(); // consume second ']'
+                    // No link text, use URL
+                    result.push_str(&url);
+                    break;
+                } else if c == ']' && chars.peek() == Some(&'[') {
+                    chars.next(); // consume '['
+                    // Extract link text
+                    let mut link_text = String::new();
+                    while let Some(t) = chars.next() {
+                        if t == ']' && chars.peek() == Some(&']') {
+                            chars.next(); // consume second ']'
+                            result.push_str(&link_text);
+                            break;
+                        } else {
+                            link_text.push(t);
+                        }
+                    }
+                    break;
+                } else {
+                    url.push(c);
+                }
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn ids(text: &str) -> Vec<String> {
+        let org = Org::parse(text);
+        let anchors = HeadingAnchors::for_org(&org);
+        anchors.ids
+    }
+
+    #[test]
+    fn test_custom_id_and_deterministic_dedup() {
+        assert_eq!(
+            ids("* Setup\n* Usage\n* Setup\n* Setup\n:PROPERTIES:\n:CUSTOM_ID: install\n:END:\n"),
+            vec!["setup", "usage", "setup-2", "install"]
+        );
+    }
+
+    #[test]
+    fn test_custom_ids_are_reserved() {
+        // The first heading would slugify to the id the later heading claims
+        assert_eq!(
+            ids("* Install\n* Other\n:PROPERTIES:\n:CUSTOM_ID: install\n:END:\n"),
+            vec!["install-2", "install"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_internal_links() {
+        let org = Org::parse("* Intro\n* Deep Dive\n:PROPERTIES:\n:CUSTOM_ID: dive\n:END:\n");
+        let anchors = HeadingAnchors::for_org(&org);
+        assert_eq!(anchors.resolve("#dive"), Some(("dive", "Deep Dive")));
+        assert_eq!(anchors.resolve("*Intro"), Some(("intro", "Intro")));
+        assert_eq!(anchors.resolve("*Missing"), None);
+        assert_eq!(anchors.resolve("https://example.com"), None);
+    }
+}
@@ -0,0 +1,390 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+/// Entries of a `.bib` file, keyed by citation key
+#[derive(Debug, Default)]
+pub struct Bibliography {
+    entries: HashMap<String, BibEntry>,
+}
+
+/// One `@type{key, field = value, ...}` entry, field names lowercased and values cleaned of TeX markup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BibEntry {
+    pub kind: String,
+    pub fields: HashMap<String, String>,
+}
+
+impl Bibliography {
+    /// Parse BibTeX source, `@string` abbreviations are substituted and `@comment`/`@preamble` skipped
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser { source, position: 0 };
+        let mut strings: HashMap<String, String> = HashMap::new();
+        let mut entries = HashMap::new();
+
+        // Anything outside an @entry is a comment, and so are lines starting with %
+        while let Some(offset) = parser.rest().find('@') {
+            parser.position += offset + 1;
+            let line_start = source[..parser.position].rfind('\n').map_or(0, |newline| newline + 1);
+            if source[line_start..].trim_start().starts_with('%') {
+                continue;
+            }
+            let line = source[..parser.position].lines().count();
+
+            let kind = parser.identifier().to_lowercase();
+            if kind == "comment" {
+                continue;
+            }
+            let (open, close) = match parser.peek() {
+                Some('{') => ('{', '}'),
+                Some('(') => ('(', ')'),
+                _ => bail!("Expected '{{' after @{} on line {}", kind, line),
+            };
+            parser.position += open.len_utf8();
+
+            match kind.as_str() {
+                "preamble" => {
+                    parser.value(&strings).with_context(|| format!("Invalid @preamble on line {}", line))?;
+                }
+                "string" => {
+                    let name = parser.identifier().to_lowercase();
+                    parser.expect('=').with_context(|| format!("Invalid @string on line {}", line))?;
+                    let value = parser.value(&strings).with_context(|| format!("Invalid @string on line {}", line))?;
+                    strings.insert(name, value);
+                }
+                _ => {
+                    let key = parser.key();
+                    if key.is_empty() {
+                        bail!("@{} on line {} has no citation key", kind, line);
+                    }
+                    let fields = parser
+                        .fields(&strings, close)
+                        .with_context(|| format!("Invalid entry '{}' on line {}", key, line))?;
+                    if entries.insert(key.clone(), BibEntry { kind, fields }).is_some() {
+                        bail!("Duplicate citation key '{}' on line {}", key, line);
+                    }
+                    continue;
+                }
+            }
+            parser.expect(close).with_context(|| format!("Unclosed @{} on line {}", kind, line))?;
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BibEntry> {
+        self.entries.get(key)
+    }
+}
+
+impl BibEntry {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str).filter(|value| !value.is_empty())
+    }
+
+    /// Authors (or editors) as written, `Last, First` turned into `First Last`
+    pub fn authors(&self) -> Vec<String> {
+        self.people()
+            .into_iter()
+            .map(|name| match name.split_once(',') {
+                Some((last, first)) => format!("{} {}", first.trim(), last.trim()),
+                None => name,
+            })
+            .collect()
+    }
+
+    /// Family names of the authors, for author-year citations
+    pub fn last_names(&self) -> Vec<String> {
+        self.people()
+            .into_iter()
+            .map(|name| match name.split_once(',') {
+                Some((last, _)) => last.trim().to_string(),
+                None => name.rsplit(' ').next().unwrap_or_default().to_string(),
+            })
+            .collect()
+    }
+
+    pub fn year(&self) -> Option<&str> {
+        self.field("year")
+            .or_else(|| self.field("date").map(|date| date.split('-').next().unwrap_or(date)))
+    }
+
+    /// Where the work appeared: journal, proceedings, publisher or series and number
+    pub fn venue(&self) -> Option<String> {
+        if let Some(venue) = ["journal", "booktitle", "howpublished"].iter().find_map(|name| self.field(name)) {
+            return Some(venue.to_string());
+        }
+        match (self.field("series"), self.field("number")) {
+            (Some(series), Some(number)) => Some(format!("{} {}", series, number)),
+            _ => ["publisher", "institution", "organization", "school"]
+                .iter()
+                .find_map(|name| self.field(name))
+                .map(str::to_string),
+        }
+    }
+
+    /// A DOI link, or else the entry's URL
+    pub fn link(&self) -> Option<String> {
+        match self.field("doi") {
+            Some(doi) => Some(format!("https://doi.org/{}", doi.trim_start_matches("https://doi.org/"))),
+            None => self.field("url").map(str::to_string),
+        }
+    }
+
+    fn people(&self) -> Vec<String> {
+        let names = self.fields.get("author").or_else(|| self.fields.get("editor"));
+        let Some(names) = names else {
+            return Vec::new();
+        };
+        names
+            .split(" and ")
+            .map(|name| name.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.source[self.position..]
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += c.len_utf8();
+                Ok(())
+            }
+            Some(c) => bail!("Expected '{}', found '{}'", expected, c),
+            None => bail!("Expected '{}', found the end of the file", expected),
+        }
+    }
+
+    /// Letters, digits and `_-:.` - entry types, field and string names
+    fn identifier(&mut self) -> &str {
+        self.take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'))
+    }
+
+    /// Citation keys may use any printable character except the delimiters
+    fn key(&mut self) -> String {
+        let key = self.take_while(|c| !c.is_whitespace() && !matches!(c, ',' | '}' | ')')).to_string();
+        if self.peek() == Some(',') {
+            self.position += 1;
+        }
+        key
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &str {
+        self.skip_whitespace();
+        let start = self.position;
+        let len: usize = self.rest().chars().take_while(|c| accept(*c)).map(char::len_utf8).sum();
+        self.position += len;
+        &self.source[start..self.position]
+    }
+
+    /// `name = value` pairs up to the entry's closing delimiter, a trailing comma is allowed
+    fn fields(&mut self, strings: &HashMap<String, String>, close: char) -> Result<HashMap<String, String>> {
+        let mut fields = HashMap::new();
+        loop {
+            if self.peek() == Some(close) {
+                self.position += close.len_utf8();
+                return Ok(fields);
+            }
+            let name = self.identifier().to_lowercase();
+            if name.is_empty() {
+                match self.peek() {
+                    Some(c) => bail!("Expected a field name, found '{}'", c),
+                    None => bail!("Entry is not closed"),
+                }
+            }
+            self.expect('=')?;
+            let value = self.value(strings).with_context(|| format!("Invalid value for field '{}'", name))?;
+            fields.insert(name, value);
+
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(c) if c == close => {}
+                Some(c) => bail!("Expected ',' after a field, found '{}'", c),
+                None => bail!("Entry is not closed"),
+            }
+        }
+    }
+
+    /// A `{braced}`, `"quoted"`, numeric or `@string` value, with `#` concatenation
+    fn value(&mut self, strings: &HashMap<String, String>) -> Result<String> {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('{') => {
+                    self.position += 1;
+                    value.push_str(&self.delimited('}')?);
+                }
+                Some('"') => {
+                    self.position += 1;
+                    value.push_str(&self.delimited('"')?);
+                }
+                Some(c) if c.is_ascii_digit() => value.push_str(self.take_while(|c| c.is_ascii_digit())),
+                Some(c) if c.is_alphabetic() => {
+                    let name = self.identifier().to_lowercase();
+                    match strings.get(&name).map(String::as_str).or_else(|| month(&name)) {
+                        Some(expansion) => value.push_str(expansion),
+                        None => bail!("Unknown @string '{}'", name),
+                    }
+                }
+                _ => bail!("Expected a value"),
+            }
+            if self.peek() != Some('#') {
+                return Ok(clean_tex(&value));
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Text up to `end` at brace depth zero, braces kept for `clean_tex`
+    fn delimited(&mut self, end: char) -> Result<String> {
+        let mut depth = 0;
+        for (offset, c) in self.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                c if c == end && depth == 0 => {
+                    let text = self.rest()[..offset].to_string();
+                    self.position += offset + c.len_utf8();
+                    return Ok(text);
+                }
+                _ => {}
+            }
+        }
+        bail!("Unbalanced braces or quotes")
+    }
+}
+
+/// Predefined month abbreviations (`month = jan`)
+fn month(name: &str) -> Option<&'static str> {
+    const MONTHS: [(&str, &str); 12] = [
+        ("jan", "January"),
+        ("feb", "February"),
+        ("mar", "March"),
+        ("apr", "April"),
+        ("may", "May"),
+        ("jun", "June"),
+        ("jul", "July"),
+        ("aug", "August"),
+        ("sep", "September"),
+        ("oct", "October"),
+        ("nov", "November"),
+        ("dec", "December"),
+    ];
+    MONTHS.iter().find(|(abbreviation, _)| *abbreviation == name).map(|(_, month)| *month)
+}
+
+/// Drop protective braces and turn common TeX escapes and dashes into plain text
+fn clean_tex(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '\\' => match chars.next() {
+                Some(escaped @ ('&' | '%' | '$' | '#' | '_' | '{' | '}')) => text.push(escaped),
+                // Commands like \emph are dropped, their braced argument stays
+                Some(c) if c.is_alphabetic() => while chars.next_if(|c| c.is_alphabetic()).is_some() {},
+                // Accents like \"o and \'e keep only the letter
+                Some(c) if !c.is_whitespace() => {}
+                _ => text.push(' '),
+            },
+            '~' => text.push('\u{a0}'),
+            '-' if chars.next_if_eq(&'-').is_some() => {
+                text.push(if chars.next_if_eq(&'-').is_some() { '—' } else { '–' });
+            }
+            c if c.is_whitespace() => {
+                if !text.ends_with(' ') {
+                    text.push(' ');
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SOURCE: &str = r#"
+Comments outside entries are ignored.
+% So are %-comments, even with an @sign
+@string{acm = "Communications of the {ACM}"}
+
+@article{knuth1984,
+  author  = {Knuth, Donald E.},
+  title   = {Literate {Programming}},
+  journal = "The Computer Journal",
+  year    = 1984,
+  month   = jan,
+  doi     = {10.1093/comjnl/27.2.97},
+}
+
+@misc{rfc9110,
+  author = {Fielding, Roy T. and Nottingham, Mark and Julian F. Reschke},
+  title  = {{HTTP} Semantics},
+  series = {Request for Comments}, number = {9110},
+  url    = {https://www.rfc-editor.org/rfc/rfc9110},
+  date   = {2022-06}
+}
+@comment{ignored, this = is not parsed}
+@inproceedings(hoare, author = {C. A. R. Hoare}, title = "Pages 1--10 \& more", booktitle = acm # { Press})
+"#;
+
+    #[test]
+    fn test_parse_entries() {
+        let bibliography = Bibliography::parse(SOURCE).unwrap();
+
+        let knuth = bibliography.get("knuth1984").unwrap();
+        assert_eq!(knuth.kind, "article");
+        assert_eq!(knuth.field("title"), Some("Literate Programming"));
+        assert_eq!(knuth.field("month"), Some("January"));
+        assert_eq!(knuth.year(), Some("1984"));
+        assert_eq!(knuth.link().as_deref(), Some("https://doi.org/10.1093/comjnl/27.2.97"));
+
+        let hoare = bibliography.get("hoare").unwrap();
+        assert_eq!(hoare.field("title"), Some("Pages 1–10 & more"));
+        assert_eq!(hoare.venue().as_deref(), Some("Communications of the ACM Press"));
+        assert!(bibliography.get("ignored").is_none());
+    }
+
+    #[test]
+    fn test_people() {
+        let bibliography = Bibliography::parse(SOURCE).unwrap();
+        let rfc = bibliography.get("rfc9110").unwrap();
+        assert_eq!(rfc.authors(), vec!["Roy T. Fielding", "Mark Nottingham", "Julian F. Reschke"]);
+        assert_eq!(rfc.last_names(), vec!["Fielding", "Nottingham", "Reschke"]);
+        assert_eq!(rfc.year(), Some("2022"));
+        assert_eq!(rfc.venue().as_deref(), Some("Request for Comments 9110"));
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let error = Bibliography::parse("@article{a, title = {ok}}\n\n@book{b, title = {unclosed}\n").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid entry 'b' on line 3");
+
+        let error = Bibliography::parse("@book{a, title = {x}}\n@book{a, title = {y}}").unwrap_err();
+        assert_eq!(error.to_string(), "Duplicate citation key 'a' on line 2");
+    }
+}
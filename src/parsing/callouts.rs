@@ -0,0 +1,71 @@
+use crate::models::CalloutStyle;
+
+/// Icon names callouts can use, see `CalloutStyle::icon`
+const ICONS: [&str; 5] = ["note", "tip", "important", "warning", "danger"];
+
+/// Resolve the icon for a callout block, falling back to "note" for unknown names
+pub fn icon_name<'a>(block_name: &'a str, style: &'a CalloutStyle) -> &'a str {
+    let icon = style.icon.as_deref().unwrap_or(block_name);
+    if ICONS.contains(&icon) {
+        icon
+    } else {
+        "note"
+    }
+}
+
+/// Inline SVG icon drawn in the callout's accent color
+pub fn icon_svg(icon: &str) -> &'static str {
+    match icon {
+        "tip" => concat!(
+            "<svg class=\"callout-icon\" viewBox=\"0 0 24 24\" aria-hidden=\"true\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">",
+            "<path d=\"M9 18h6M10 22h4M12 2a7 7 0 0 0-4 12.7V17h8v-2.3A7 7 0 0 0 12 2z\"/></svg>"
+        ),
+        "important" => concat!(
+            "<svg class=\"callout-icon\" viewBox=\"0 0 24 24\" aria-hidden=\"true\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">",
+            "<path d=\"M21 15a2 2 0 0 1-2 2H7l-4 4V5a2 2 0 0 1 2-2h14a2 2 0 0 1 2 2z\"/><path d=\"M12 7v4M12 14h.01\"/></svg>"
+        ),
+        "warning" => concat!(
+            "<svg class=\"callout-icon\" viewBox=\"0 0 24 24\" aria-hidden=\"true\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">",
+            "<path d=\"M10.3 3.9 1.8 18a2 2 0 0 0 1.7 3h17a2 2 0 0 0 1.7-3L13.7 3.9a2 2 0 0 0-3.4 0z\"/><path d=\"M12 9v4M12 17h.01\"/></svg>"
+        ),
+        "danger" => concat!(
+            "<svg class=\"callout-icon\" viewBox=\"0 0 24 24\" aria-hidden=\"true\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">",
+            "<path d=\"M7.9 2h8.2L22 7.9v8.2L16.1 22H7.9L2 16.1V7.9z\"/><path d=\"M15 9l-6 6M9 9l6 6\"/></svg>"
+        ),
+        _ => concat!(
+            "<svg class=\"callout-icon\" viewBox=\"0 0 24 24\" aria-hidden=\"true\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">",
+            "<circle cx=\"12\" cy=\"12\" r=\"10\"/><path d=\"M12 16v-4M12 8h.01\"/></svg>"
+        ),
+    }
+}
+
+/// Emoji stand-in for the icon in feeds, where the SVG would lose its styling
+pub fn icon_emoji(icon: &str) -> &'static str {
+    match icon {
+        "tip" => "💡",
+        "important" => "❗",
+        "warning" => "⚠️",
+        "danger" => "🛑",
+        _ => "ℹ️",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(icon: Option<&str>) -> CalloutStyle {
+        CalloutStyle {
+            title: "Title".to_string(),
+            icon: icon.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_icon_name_resolution() {
+        assert_eq!(icon_name("warning", &style(None)), "warning");
+        assert_eq!(icon_name("security", &style(Some("danger"))), "danger");
+        assert_eq!(icon_name("aside", &style(None)), "note");
+        assert_eq!(icon_name("aside", &style(Some("sparkles"))), "note");
+    }
+}
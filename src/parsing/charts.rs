@@ -0,0 +1,508 @@
+use orgize::elements::{TableCell, TableRow};
+use orgize::Element;
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 360.0;
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 16.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const LEGEND_HEIGHT: f64 = 24.0;
+// Categorical x labels beyond this are thinned out so they don't overlap
+const MAX_X_LABELS: usize = 12;
+
+// Series colors: theme variables from style.css, with fallbacks for feed readers
+const PALETTE: [(&str, &str); 5] = [
+    ("--chart-1", "#3a8fd9"),
+    ("--chart-2", "#d9733a"),
+    ("--chart-3", "#3aa66b"),
+    ("--chart-4", "#a65cd9"),
+    ("--chart-5", "#c9a227"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+    Scatter,
+}
+
+impl ChartKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::Bar => "bar",
+            Self::Scatter => "scatter",
+        }
+    }
+}
+
+/// Chart requested by `#+ATTR_CHART:` above a table, e.g. `:type line :x 1 :y 2,3`
+#[derive(Debug, PartialEq)]
+pub struct ChartSpec {
+    pub kind: ChartKind,
+    /// 1-based column holding the x values or categories
+    pub x: usize,
+    /// 1-based columns plotted as series; empty means every column except `x`
+    pub y: Vec<usize>,
+}
+
+impl ChartSpec {
+    pub fn parse(attributes: &str) -> Result<Self, String> {
+        let mut spec = Self {
+            kind: ChartKind::Line,
+            x: 1,
+            y: Vec::new(),
+        };
+
+        let mut tokens = attributes.split_whitespace();
+        while let Some(key) = tokens.next() {
+            let value = tokens
+                .next()
+                .ok_or_else(|| format!("Missing value for chart attribute '{}'", key))?;
+            match key {
+                ":type" => {
+                    spec.kind = match value {
+                        "line" => ChartKind::Line,
+                        "bar" => ChartKind::Bar,
+                        "scatter" => ChartKind::Scatter,
+                        other => {
+                            return Err(format!(
+                                "Unknown chart type '{}' (expected line, bar or scatter)",
+                                other
+                            ))
+                        }
+                    }
+                }
+                ":x" => spec.x = parse_column(value)?,
+                ":y" => spec.y = value.split(',').map(parse_column).collect::<Result<_, _>>()?,
+                other => return Err(format!("Unknown chart attribute '{}'", other)),
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
+fn parse_column(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(column) if column > 0 => Ok(column),
+        _ => Err(format!("Invalid column '{}' (columns are numbered from 1)", value)),
+    }
+}
+
+/// Cell text of an org table, collected from its element events
+#[derive(Debug, Default)]
+pub struct TableData {
+    pub header: Vec<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    in_header: bool,
+}
+
+impl TableData {
+    pub fn record(&mut self, element: &Element) {
+        match element {
+            Element::TableRow(TableRow::Header) => {
+                self.header.push(Vec::new());
+                self.in_header = true;
+            }
+            Element::TableRow(TableRow::Body) => {
+                self.rows.push(Vec::new());
+                self.in_header = false;
+            }
+            Element::TableCell(TableCell::Header | TableCell::Body) => {
+                if let Some(row) = self.current_row() {
+                    row.push(String::new());
+                }
+            }
+            Element::Text { value } | Element::Code { value } | Element::Verbatim { value } => {
+                if let Some(cell) = self.current_row().and_then(|row| row.last_mut()) {
+                    cell.push_str(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn current_row(&mut self) -> Option<&mut Vec<String>> {
+        if self.in_header {
+            self.header.last_mut()
+        } else {
+            self.rows.last_mut()
+        }
+    }
+
+    fn cell(&self, row: usize, column: usize) -> &str {
+        self.rows[row].get(column - 1).map(|cell| cell.trim()).unwrap_or("")
+    }
+
+    fn column_name(&self, column: usize) -> String {
+        self.header
+            .first()
+            .and_then(|header| header.get(column - 1))
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Column {}", column))
+    }
+}
+
+struct Series {
+    name: String,
+    values: Vec<Option<f64>>,
+}
+
+/// Linear mapping from data values onto an SVG coordinate range
+struct Scale {
+    domain: (f64, f64),
+    range: (f64, f64),
+}
+
+impl Scale {
+    fn map(&self, value: f64) -> f64 {
+        let (d0, d1) = self.domain;
+        let (r0, r1) = self.range;
+        r0 + (value - d0) / (d1 - d0) * (r1 - r0)
+    }
+}
+
+/// Render the table as an SVG chart, or explain why its data cannot be plotted
+pub fn render_chart(spec: &ChartSpec, table: &TableData, title: Option<&str>) -> Result<String, String> {
+    if table.rows.is_empty() {
+        return Err("Table has no data rows".to_string());
+    }
+
+    let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    for column in spec.y.iter().chain([&spec.x]) {
+        if *column > columns {
+            return Err(format!("Column {} does not exist (table has {} columns)", column, columns));
+        }
+    }
+    let y_columns: Vec<usize> = if spec.y.is_empty() {
+        (1..=columns).filter(|column| *column != spec.x).collect()
+    } else {
+        spec.y.clone()
+    };
+
+    let mut series = Vec::new();
+    for &column in &y_columns {
+        let values = (0..table.rows.len())
+            .map(|row| match table.cell(row, column) {
+                "" => Ok(None),
+                cell => parse_number(cell).map(Some).ok_or_else(|| {
+                    format!("Row {}, column {}: '{}' is not a number", row + 1, column, cell)
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        series.push(Series {
+            name: table.column_name(column),
+            values,
+        });
+    }
+
+    let x_labels: Vec<&str> = (0..table.rows.len()).map(|row| table.cell(row, spec.x)).collect();
+    // Line and scatter charts place numeric x values proportionally, everything else is categorical
+    let x_values: Option<Vec<f64>> = match spec.kind {
+        ChartKind::Bar => None,
+        ChartKind::Line | ChartKind::Scatter => x_labels.iter().map(|label| parse_number(label)).collect(),
+    };
+
+    let all_values = series.iter().flat_map(|s| s.values.iter().flatten().copied());
+    let (mut y_min, mut y_max) = min_max(all_values).ok_or("No numeric values to plot")?;
+    if spec.kind == ChartKind::Bar {
+        y_min = y_min.min(0.0);
+        y_max = y_max.max(0.0);
+    }
+    let y_ticks = nice_ticks(y_min, y_max);
+
+    let legend = if series.len() > 1 { LEGEND_HEIGHT } else { 0.0 };
+    let left = MARGIN_LEFT;
+    let right = WIDTH - MARGIN_RIGHT;
+    let top = MARGIN_TOP;
+    let bottom = HEIGHT - MARGIN_BOTTOM - legend;
+
+    let y_scale = Scale {
+        domain: (y_ticks[0], y_ticks[y_ticks.len() - 1]),
+        range: (bottom, top),
+    };
+
+    let label = match title {
+        Some(title) => title.to_string(),
+        None => format!(
+            "{} chart of {}",
+            capitalize(spec.kind.name()),
+            series.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg class=\"chart-svg\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\" role=\"img\" \
+         aria-label=\"{}\" xmlns=\"http://www.w3.org/2000/svg\" font-family=\"sans-serif\" font-size=\"12\">",
+        html_escape::encode_double_quoted_attribute(&label),
+        w = WIDTH,
+        h = HEIGHT,
+    );
+
+    // Horizontal grid lines with y axis labels
+    for tick in &y_ticks {
+        let y = y_scale.map(*tick);
+        let _ = write!(
+            svg,
+            "<line x1=\"{left:.1}\" y1=\"{y:.1}\" x2=\"{right:.1}\" y2=\"{y:.1}\" stroke=\"currentColor\" stroke-opacity=\"0.15\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" fill=\"currentColor\">{}</text>",
+            left - 8.0,
+            y + 4.0,
+            format_number(*tick),
+        );
+    }
+    let _ = write!(
+        svg,
+        "<line x1=\"{left:.1}\" y1=\"{bottom:.1}\" x2=\"{right:.1}\" y2=\"{bottom:.1}\" stroke=\"currentColor\" stroke-opacity=\"0.6\"/>"
+    );
+
+    // x positions for each row, plus the x axis labels
+    let rows = table.rows.len();
+    let band = (right - left) / rows as f64;
+    let x_positions: Vec<f64> = match &x_values {
+        Some(values) => {
+            let (x_min, x_max) = min_max(values.iter().copied()).unwrap_or((0.0, 1.0));
+            let x_ticks = nice_ticks(x_min, x_max);
+            let x_scale = Scale {
+                domain: (x_ticks[0], x_ticks[x_ticks.len() - 1]),
+                range: (left, right),
+            };
+            for tick in &x_ticks {
+                write_x_label(&mut svg, x_scale.map(*tick), bottom, &format_number(*tick));
+            }
+            values.iter().map(|value| x_scale.map(*value)).collect()
+        }
+        None => {
+            let step = rows.div_ceil(MAX_X_LABELS);
+            for (row, label) in x_labels.iter().enumerate().step_by(step) {
+                write_x_label(&mut svg, left + band * (row as f64 + 0.5), bottom, label);
+            }
+            (0..rows).map(|row| left + band * (row as f64 + 0.5)).collect()
+        }
+    };
+
+    for (index, s) in series.iter().enumerate() {
+        let color = series_color(index);
+        let name = html_escape::encode_text(&s.name);
+        let _ = write!(svg, "<g class=\"chart-series\"><title>{}</title>", name);
+
+        match spec.kind {
+            ChartKind::Line => {
+                let mut path = String::new();
+                let mut pen_down = false;
+                for (x, value) in x_positions.iter().zip(&s.values) {
+                    match value {
+                        Some(value) => {
+                            let command = if pen_down { 'L' } else { 'M' };
+                            let _ = write!(path, "{}{:.1} {:.1} ", command, x, y_scale.map(*value));
+                            pen_down = true;
+                        }
+                        // Empty cells leave a gap in the line
+                        None => pen_down = false,
+                    }
+                }
+                let _ = write!(
+                    svg,
+                    "<path d=\"{}\" fill=\"none\" stroke-width=\"2\" style=\"stroke:{}\"/>",
+                    path.trim_end(),
+                    color
+                );
+                write_points(&mut svg, &x_positions, &s.values, &y_scale, 3.0, &color);
+            }
+            ChartKind::Scatter => {
+                write_points(&mut svg, &x_positions, &s.values, &y_scale, 4.0, &color);
+            }
+            ChartKind::Bar => {
+                let group = band * 0.8;
+                let bar_width = group / series.len() as f64;
+                let zero = y_scale.map(0.0);
+                for (x, value) in x_positions.iter().zip(&s.values) {
+                    let Some(value) = value else { continue };
+                    let y = y_scale.map(*value);
+                    let _ = write!(
+                        svg,
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" style=\"fill:{}\"/>",
+                        x - group / 2.0 + bar_width * index as f64,
+                        y.min(zero),
+                        bar_width,
+                        (y - zero).abs(),
+                        color
+                    );
+                }
+            }
+        }
+
+        svg.push_str("</g>");
+    }
+
+    if series.len() > 1 {
+        let mut x = left;
+        let y = HEIGHT - LEGEND_HEIGHT / 2.0;
+        for (index, s) in series.iter().enumerate() {
+            let _ = write!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"10\" height=\"10\" style=\"fill:{}\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\" fill=\"currentColor\">{}</text>",
+                x,
+                y - 9.0,
+                series_color(index),
+                x + 14.0,
+                y,
+                html_escape::encode_text(&s.name)
+            );
+            // Rough advance for a 12px sans-serif label
+            x += 14.0 + s.name.chars().count() as f64 * 7.0 + 16.0;
+        }
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+fn write_x_label(svg: &mut String, x: f64, bottom: f64, label: &str) {
+    let _ = write!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"currentColor\">{}</text>",
+        x,
+        bottom + 18.0,
+        html_escape::encode_text(label)
+    );
+}
+
+fn write_points(svg: &mut String, xs: &[f64], values: &[Option<f64>], y_scale: &Scale, radius: f64, color: &str) {
+    for (x, value) in xs.iter().zip(values) {
+        if let Some(value) = value {
+            let _ = write!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" style=\"fill:{}\"/>",
+                x,
+                y_scale.map(*value),
+                radius,
+                color
+            );
+        }
+    }
+}
+
+fn series_color(index: usize) -> String {
+    let (variable, fallback) = PALETTE[index % PALETTE.len()];
+    format!("var({}, {})", variable, fallback)
+}
+
+/// Parse a table cell as a number, allowing `_` digit separators as in "1_000"
+fn parse_number(cell: &str) -> Option<f64> {
+    cell.replace('_', "").parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    values.fold(None, |range, value| match range {
+        Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+        None => Some((value, value)),
+    })
+}
+
+/// Round axis ticks covering [min, max] in steps of 1, 2 or 5 times a power of ten
+fn nice_ticks(min: f64, max: f64) -> Vec<f64> {
+    let (min, max) = if min == max { (min - 1.0, max + 1.0) } else { (min, max) };
+    let raw_step = (max - min) / 5.0;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = magnitude
+        * match raw_step / magnitude {
+            n if n < 1.5 => 1.0,
+            n if n < 3.0 => 2.0,
+            n if n < 7.0 => 5.0,
+            _ => 10.0,
+        };
+
+    let start = (min / step).floor() * step;
+    let count = ((max - start) / step).ceil() as usize;
+    (0..=count).map(|i| start + step * i as f64).collect()
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let formatted = format!("{:.3}", value);
+        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn table(header: &[&str], rows: &[&[&str]]) -> TableData {
+        let to_row = |row: &[&str]| row.iter().map(|cell| cell.to_string()).collect();
+        TableData {
+            header: if header.is_empty() { Vec::new() } else { vec![to_row(header)] },
+            rows: rows.iter().map(|row| to_row(row)).collect(),
+            in_header: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_chart_spec() {
+        assert_eq!(
+            ChartSpec::parse(":type bar :x 2 :y 1,3").unwrap(),
+            ChartSpec {
+                kind: ChartKind::Bar,
+                x: 2,
+                y: vec![1, 3]
+            }
+        );
+        assert_eq!(ChartSpec::parse("").unwrap().kind, ChartKind::Line);
+        assert!(ChartSpec::parse(":type pie").is_err());
+        assert!(ChartSpec::parse(":y 0").is_err());
+    }
+
+    #[test]
+    fn test_nice_ticks() {
+        assert_eq!(nice_ticks(0.0, 10.0), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(nice_ticks(3.0, 3.0), vec![2.0, 2.5, 3.0, 3.5, 4.0]);
+    }
+
+    #[test]
+    fn test_render_line_chart_with_legend() {
+        let data = table(&["n", "std", "fast"], &[&["1", "10", "5"], &["2", "20", "8"], &["4", "", "12"]]);
+        let svg = render_chart(&ChartSpec::parse(":x 1 :y 2,3").unwrap(), &data, None).unwrap();
+        assert!(svg.starts_with("<svg class=\"chart-svg\""));
+        assert!(svg.contains("aria-label=\"Line chart of std, fast\""));
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(">fast</text>"));
+    }
+
+    #[test]
+    fn test_render_bar_chart() {
+        let data = table(&[], &[&["a", "3"], &["b", "-1"]]);
+        let svg = render_chart(&ChartSpec::parse(":type bar").unwrap(), &data, Some("Results")).unwrap();
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert!(svg.contains("aria-label=\"Results\""));
+    }
+
+    #[test]
+    fn test_render_chart_reports_bad_data() {
+        let data = table(&["x", "y"], &[&["1", "fast"]]);
+        assert_eq!(
+            render_chart(&ChartSpec::parse("").unwrap(), &data, None).unwrap_err(),
+            "Row 1, column 2: 'fast' is not a number"
+        );
+        assert!(render_chart(&ChartSpec::parse(":y 5").unwrap(), &data, None).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::models::CitationStyle;
+use super::bibtex::{BibEntry, Bibliography};
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+
+// [cite:@key], [cite/t:see @a p. 5; @b], the style and variant after `/` are optional
+static CITE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[cite(?:/([\w-]+))?(?:/[\w-]+)?:([^\]]*@[^\]]*)\]")
+        .expect("CITE_REGEX: hardcoded pattern is invalid")
+});
+
+static KEY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"@([\w][\w\-.:/+]*)").expect("KEY_REGEX: hardcoded pattern is invalid")
+});
+
+/// In-text citations for one document, resolved against the site's bibliography
+/// Keeps track of what was cited so the references list can be written at the end
+#[derive(Debug, Default)]
+pub struct Citations {
+    bibliography: Arc<Bibliography>,
+    style: CitationStyle,
+    // Keys in order of first citation, which is also the numeric style's numbering
+    cited: Vec<String>,
+    // Keys missing from the bibliography
+    unknown_keys: Vec<String>,
+}
+
+/// One cited key with its own `prefix @key suffix` text
+#[derive(Debug, PartialEq)]
+struct Reference<'a> {
+    key: &'a str,
+    prefix: &'a str,
+    suffix: &'a str,
+}
+
+impl Citations {
+    pub fn new(bibliography: Arc<Bibliography>, style: CitationStyle) -> Self {
+        Self {
+            bibliography,
+            style,
+            ..Self::default()
+        }
+    }
+
+    pub fn unknown_keys(&self) -> &[String] {
+        &self.unknown_keys
+    }
+
+    /// Replace `[cite:...]` in plain text with citation HTML, the text around them goes through `plain`
+    pub fn render(&mut self, text: &str, mut plain: impl FnMut(&str) -> String) -> String {
+        let mut html = String::with_capacity(text.len());
+        let mut last = 0;
+
+        for captures in CITE_REGEX.captures_iter(text) {
+            let citation = captures.get(0).expect("capture 0 is the whole match");
+            let textual = matches!(captures.get(1).map(|style| style.as_str()), Some("t" | "text"));
+            let references = parse_references(captures.get(2).map_or("", |body| body.as_str()));
+
+            html.push_str(&plain(&text[last..citation.start()]));
+            html.push_str(&self.cite(&references, textual, citation.as_str()));
+            last = citation.end();
+        }
+
+        html.push_str(&plain(&text[last..]));
+        html
+    }
+
+    fn cite(&mut self, references: &[Reference], textual: bool, source: &str) -> String {
+        let mut found = Vec::new();
+        for reference in references {
+            match self.bibliography.get(reference.key) {
+                Some(entry) => {
+                    if !self.cited.iter().any(|key| key == reference.key) {
+                        self.cited.push(reference.key.to_string());
+                    }
+                    found.push((reference, entry));
+                }
+                None => {
+                    if !self.unknown_keys.iter().any(|key| key == reference.key) {
+                        self.unknown_keys.push(reference.key.to_string());
+                    }
+                }
+            }
+        }
+        // Unknown keys stop the site from starting, this only shows up in tests
+        if found.len() < references.len() {
+            return format!("<span class=\"citation broken-citation\">{}</span>", html_escape::encode_text(source));
+        }
+
+        let cited: Vec<String> = found
+            .iter()
+            .map(|(reference, entry)| {
+                let href = format!("#ref-{}", html_escape::encode_double_quoted_attribute(reference.key));
+                let prefix = format_prefix(reference.prefix);
+                let suffix = format_suffix(reference.suffix);
+                let number = self.cited.iter().position(|key| key == reference.key).unwrap_or_default() + 1;
+                match (self.style, textual) {
+                    (CitationStyle::Numeric, false) => format!("{}<a href=\"{}\">{}</a>{}", prefix, href, number, suffix),
+                    (CitationStyle::Numeric, true) => format!(
+                        "{}{} [<a href=\"{}\">{}</a>{}]",
+                        prefix,
+                        html_escape::encode_text(&short_authors(entry, reference.key)),
+                        href,
+                        number,
+                        suffix
+                    ),
+                    (CitationStyle::AuthorYear, false) => format!(
+                        "{}<a href=\"{}\">{}, {}</a>{}",
+                        prefix,
+                        href,
+                        html_escape::encode_text(&short_authors(entry, reference.key)),
+                        html_escape::encode_text(entry.year().unwrap_or("n.d.")),
+                        suffix
+                    ),
+                    (CitationStyle::AuthorYear, true) => format!(
+                        "{}<a href=\"{}\">{}</a> ({}{})",
+                        prefix,
+                        href,
+                        html_escape::encode_text(&short_authors(entry, reference.key)),
+                        html_escape::encode_text(entry.year().unwrap_or("n.d.")),
+                        suffix
+                    ),
+                }
+            })
+            .collect();
+
+        let cited = match (self.style, textual) {
+            (_, true) => cited.join(", "),
+            (CitationStyle::Numeric, false) => format!("[{}]", cited.join(", ")),
+            (CitationStyle::AuthorYear, false) => format!("({})", cited.join("; ")),
+        };
+        format!("<span class=\"citation\">{}</span>", cited)
+    }
+
+    /// The references list for everything cited so far, `None` if nothing was
+    pub fn references_html(&self) -> Option<String> {
+        if self.cited.is_empty() {
+            return None;
+        }
+
+        let mut entries: Vec<(&str, &BibEntry)> = self
+            .cited
+            .iter()
+            .filter_map(|key| Some((key.as_str(), self.bibliography.get(key)?)))
+            .collect();
+        let (tag, class) = match self.style {
+            CitationStyle::Numeric => ("ol", "references"),
+            CitationStyle::AuthorYear => {
+                entries.sort_by_key(|(key, entry)| {
+                    (entry.last_names().join(" ").to_lowercase(), entry.year().map(str::to_string), key.to_string())
+                });
+                ("ul", "references references-author-year")
+            }
+        };
+
+        let items: String = entries
+            .iter()
+            .map(|(key, entry)| {
+                format!(
+                    "<li id=\"ref-{}\">{}</li>",
+                    html_escape::encode_double_quoted_attribute(key),
+                    self.format_entry(key, entry)
+                )
+            })
+            .collect();
+        Some(format!("<{} class=\"{}\">{}</{}>", tag, class, items, tag))
+    }
+
+    /// `Authors. Title. Venue, Year. Link` or, author-year, `Authors (Year). Title. Venue. Link`
+    fn format_entry(&self, key: &str, entry: &BibEntry) -> String {
+        let authors = join_names(&entry.authors());
+        let year = entry.year().map(str::to_string);
+        let title = format!("<cite>{}</cite>", html_escape::encode_text(entry.field("title").unwrap_or(key)));
+
+        let mut parts = Vec::new();
+        match self.style {
+            CitationStyle::Numeric => {
+                if !authors.is_empty() {
+                    parts.push(html_escape::encode_text(&authors).into_owned());
+                }
+                parts.push(title);
+                let published: Vec<String> = entry.venue().into_iter().chain(year).collect();
+                if !published.is_empty() {
+                    parts.push(html_escape::encode_text(&published.join(", ")).into_owned());
+                }
+            }
+            CitationStyle::AuthorYear => {
+                let year = year.as_deref().unwrap_or("n.d.");
+                let byline = if authors.is_empty() { year.to_string() } else { format!("{} ({})", authors, year) };
+                parts.push(html_escape::encode_text(&byline).into_owned());
+                parts.push(title);
+                if let Some(venue) = entry.venue() {
+                    parts.push(html_escape::encode_text(&venue).into_owned());
+                }
+            }
+        }
+
+        let mut html = parts.join(". ");
+        html.push('.');
+        if let Some(link) = entry.link() {
+            let link = html_escape::encode_double_quoted_attribute(&link).into_owned();
+            html.push_str(&format!(" <a href=\"{}\">{}</a>", link, link));
+        }
+        html
+    }
+}
+
+/// Split a citation body on `;`, a leading or trailing part without a key applies to the whole citation
+fn parse_references(body: &str) -> Vec<Reference<'_>> {
+    let parts: Vec<&str> = body.split(';').collect();
+    let mut references: Vec<Reference> = parts
+        .iter()
+        .filter_map(|part| {
+            let key = KEY_REGEX.captures(part)?;
+            let (at, key) = (key.get(0)?, key.get(1)?);
+            Some(Reference {
+                key: key.as_str(),
+                prefix: part[..at.start()].trim(),
+                suffix: part[at.end()..].trim(),
+            })
+        })
+        .collect();
+
+    let global_prefix = parts.first().filter(|part| !KEY_REGEX.is_match(part)).map(|part| part.trim());
+    let global_suffix = parts.last().filter(|part| parts.len() > 1 && !KEY_REGEX.is_match(part)).map(|part| part.trim());
+    if let (Some(prefix), Some(first)) = (global_prefix, references.first_mut()) {
+        if first.prefix.is_empty() {
+            first.prefix = prefix;
+        }
+    }
+    if let (Some(suffix), Some(last)) = (global_suffix, references.last_mut()) {
+        if last.suffix.is_empty() {
+            last.suffix = suffix;
+        }
+    }
+    references
+}
+
+fn format_prefix(prefix: &str) -> String {
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", html_escape::encode_text(prefix))
+    }
+}
+
+/// `p. 5` becomes `, p. 5`, a suffix that starts with its own comma is kept as written
+fn format_suffix(suffix: &str) -> String {
+    match suffix {
+        "" => String::new(),
+        suffix if suffix.starts_with(',') => html_escape::encode_text(suffix).into_owned(),
+        suffix => format!(", {}", html_escape::encode_text(suffix)),
+    }
+}
+
+/// `Knuth`, `Fielding and Nottingham`, `Fielding et al.`; the title or key when there are no authors
+fn short_authors(entry: &BibEntry, key: &str) -> String {
+    match entry.last_names().as_slice() {
+        [] => entry.field("title").unwrap_or(key).to_string(),
+        [only] => only.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, ..] => format!("{} et al.", first),
+    }
+}
+
+/// `A`, `A and B`, `A, B and C`
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const BIB: &str = r#"
+@article{knuth1984, author = {Knuth, Donald E.}, title = {Literate Programming},
+         journal = {The Computer Journal}, year = 1984, doi = {10.1093/comjnl/27.2.97}}
+@misc{rfc9110, author = {Fielding, Roy T. and Nottingham, Mark and Reschke, Julian}, title = {HTTP Semantics},
+      series = {RFC}, number = 9110, year = 2022}
+"#;
+
+    fn citations(style: CitationStyle) -> Citations {
+        Citations::new(Arc::new(Bibliography::parse(BIB).unwrap()), style)
+    }
+
+    fn render(citations: &mut Citations, text: &str) -> String {
+        citations.render(text, |plain| html_escape::encode_text(plain).into_owned())
+    }
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(
+            parse_references("See; @a p. 5;compare @b;  and more"),
+            vec![
+                Reference { key: "a", prefix: "See", suffix: "p. 5" },
+                Reference { key: "b", prefix: "compare", suffix: "and more" },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numeric_style() {
+        let mut citations = citations(CitationStyle::Numeric);
+        assert_eq!(
+            render(&mut citations, "As in [cite:@rfc9110; see @knuth1984 p. 5] & [cite/t:@rfc9110]."),
+            "As in <span class=\"citation\">[<a href=\"#ref-rfc9110\">1</a>, see <a href=\"#ref-knuth1984\">2</a>, p. 5]</span> \
+             &amp; <span class=\"citation\">Fielding et al. [<a href=\"#ref-rfc9110\">1</a>]</span>."
+        );
+        assert_eq!(
+            citations.references_html().unwrap(),
+            "<ol class=\"references\">\
+             <li id=\"ref-rfc9110\">Roy T. Fielding, Mark Nottingham and Julian Reschke. <cite>HTTP Semantics</cite>. RFC 9110, 2022.</li>\
+             <li id=\"ref-knuth1984\">Donald E. Knuth. <cite>Literate Programming</cite>. The Computer Journal, 1984. \
+             <a href=\"https://doi.org/10.1093/comjnl/27.2.97\">https://doi.org/10.1093/comjnl/27.2.97</a></li></ol>"
+        );
+    }
+
+    #[test]
+    fn test_author_year_style() {
+        let mut citations = citations(CitationStyle::AuthorYear);
+        assert_eq!(
+            render(&mut citations, "[cite:@rfc9110;@knuth1984] and [cite/t:@knuth1984]"),
+            "<span class=\"citation\">(<a href=\"#ref-rfc9110\">Fielding et al., 2022</a>; \
+             <a href=\"#ref-knuth1984\">Knuth, 1984</a>)</span> and \
+             <span class=\"citation\"><a href=\"#ref-knuth1984\">Knuth</a> (1984)</span>"
+        );
+        // Sorted by author rather than by first citation
+        let references = citations.references_html().unwrap();
+        assert!(references.starts_with("<ul class=\"references references-author-year\"><li id=\"ref-rfc9110\">"));
+        assert!(references.contains("Donald E. Knuth (1984). <cite>Literate Programming</cite>. The Computer Journal."));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_recorded() {
+        let mut citations = citations(CitationStyle::Numeric);
+        let html = render(&mut citations, "[cite:@knuth1984;@missing] and email@example.com [cite:]");
+        assert!(html.contains("broken-citation"));
+        assert!(html.ends_with("and email@example.com [cite:]"));
+        assert_eq!(citations.unknown_keys(), ["missing"]);
+        assert_eq!(citations.references_html().map(|html| html.matches("<li").count()), Some(1));
+    }
+}
@@ -0,0 +1,187 @@
+use layout::backends::svg::SVGWriter;
+use layout::gv::{DotParser, GraphBuilder};
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::LazyLock;
+
+/// Element ids and the references to them: `id="x"`, `href="#x"` and `url(#x)`
+static SVG_ID_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r##"(\sid="|href="#|url\(#)([^")]+)"##).expect("SVG_ID_REGEX: hardcoded pattern is invalid")
+});
+
+static SVG_STYLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)(<style[^>]*>)(.*?)(</style>)").expect("SVG_STYLE_REGEX: hardcoded pattern is invalid")
+});
+
+static CSS_CLASS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\.(-?[_a-zA-Z][\w-]*)").expect("CSS_CLASS_REGEX: hardcoded pattern is invalid")
+});
+
+static CLASS_ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(\sclass=")([^"]*)"#).expect("CLASS_ATTR_REGEX: hardcoded pattern is invalid")
+});
+
+/// Source block languages rendered as diagrams instead of highlighted code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagramKind {
+    /// Graphviz `dot`, laid out with layout-rs
+    Dot,
+    /// ASCII-art diagrams converted by svgbob
+    Svgbob,
+}
+
+impl DiagramKind {
+    pub fn from_language(lang: &str) -> Option<Self> {
+        match lang {
+            "dot" | "graphviz" => Some(Self::Dot),
+            "svgbob" | "bob" => Some(Self::Svgbob),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::Svgbob => "svgbob",
+        }
+    }
+
+    /// Render the diagram source to an inline SVG, or a readable error message
+    /// The SVG's ids and stylesheet classes start with `id_prefix`, which must be unique on the page
+    pub fn render(self, source: &str, id_prefix: &str) -> Result<String, String> {
+        let svg = match self {
+            Self::Dot => render_dot(source)?,
+            Self::Svgbob => render_svgbob(source),
+        };
+        Ok(namespace_svg(&svg, id_prefix))
+    }
+}
+
+/// Both renderers emit fixed marker and path ids and a global `<style>`, so without a prefix
+/// every diagram on a page would use the markers and styles of the first one
+fn namespace_svg(svg: &str, prefix: &str) -> String {
+    let svg = SVG_ID_REGEX.replace_all(svg, |caps: &Captures| format!("{}{}-{}", &caps[1], prefix, &caps[2]));
+
+    let classes: HashSet<String> = SVG_STYLE_REGEX
+        .captures_iter(&svg)
+        .flat_map(|caps| {
+            CSS_CLASS_REGEX
+                .captures_iter(caps.get(2).map_or("", |css| css.as_str()))
+                .map(|class| class[1].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    if classes.is_empty() {
+        return svg.into_owned();
+    }
+
+    let svg = SVG_STYLE_REGEX.replace_all(&svg, |caps: &Captures| {
+        let css = CSS_CLASS_REGEX.replace_all(&caps[2], |class: &Captures| format!(".{}-{}", prefix, &class[1]));
+        format!("{}{}{}", &caps[1], css, &caps[3])
+    });
+    CLASS_ATTR_REGEX
+        .replace_all(&svg, |caps: &Captures| {
+            let names: Vec<String> = caps[2]
+                .split_whitespace()
+                .map(|name| {
+                    if classes.contains(name) {
+                        format!("{}-{}", prefix, name)
+                    } else {
+                        name.to_string()
+                    }
+                })
+                .collect();
+            format!("{}{}", &caps[1], names.join(" "))
+        })
+        .into_owned()
+}
+
+fn render_dot(source: &str) -> Result<String, String> {
+    let graph = DotParser::new(source)
+        .process()
+        .map_err(|e| format!("Failed to parse graph: {}", e))?;
+
+    // layout-rs panics on some graphs it cannot place, report those like parse errors
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut builder = GraphBuilder::new();
+        builder.visit_graph(&graph);
+        let mut visual_graph = builder.get();
+        let mut svg = SVGWriter::new();
+        visual_graph.do_it(false, false, false, &mut svg);
+        // The XML prolog is only valid at the start of a standalone file
+        let svg = svg.finalize();
+        match svg.find("<svg") {
+            Some(start) => svg[start..].to_string(),
+            None => svg,
+        }
+    }))
+    .map_err(|panic| {
+        let reason = panic
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| panic.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown layout error");
+        format!("Failed to lay out graph: {}", reason)
+    })
+}
+
+fn render_svgbob(source: &str) -> String {
+    // Draw with the surrounding text color so diagrams follow the light/dark theme
+    let settings = svgbob::Settings {
+        stroke_color: "currentColor".into(),
+        fill_color: "currentColor".into(),
+        background: "transparent".into(),
+        font_family: "JetBrains Mono, monospace".into(),
+        include_backdrop: false,
+        ..Default::default()
+    };
+    svgbob::to_svg_with_settings(source, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagram_kind_from_language() {
+        assert_eq!(DiagramKind::from_language("dot"), Some(DiagramKind::Dot));
+        assert_eq!(DiagramKind::from_language("svgbob"), Some(DiagramKind::Svgbob));
+        assert_eq!(DiagramKind::from_language("rust"), None);
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let svg = DiagramKind::Dot.render("digraph { a -> b; }", "d1").expect("Should lay out");
+        assert!(svg.starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_render_dot_reports_parse_errors() {
+        let error = DiagramKind::Dot.render("digraph { a -> ; }", "d1").unwrap_err();
+        assert!(error.starts_with("Failed to parse graph"));
+    }
+
+    #[test]
+    fn test_render_svgbob() {
+        let svg = DiagramKind::Svgbob.render("+--+\n|  |\n+--+\n", "d1").expect("Should render");
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    fn test_render_namespaces_ids_and_classes() {
+        let svg = DiagramKind::Dot.render("digraph { a -> b [label=\"x\"]; }", "post-diagram-2").unwrap();
+        assert!(svg.contains(r#"<marker id="post-diagram-2-endarrow""#));
+        assert!(svg.contains(r#"id="post-diagram-2-arrow0""#));
+        assert!(svg.contains(r##"href="#post-diagram-2-arrow0""##));
+        assert!(svg.contains("marker-end=\"url(#post-diagram-2-endarrow)\""));
+        assert!(svg.contains(".post-diagram-2-a14 {"));
+        assert!(svg.contains(r#"class="post-diagram-2-a14""#));
+        assert!(!svg.contains(r#"id="endarrow""#) && !svg.contains(r#"class="a14""#));
+
+        let svg = DiagramKind::Svgbob.render("+--+\n|  |-->\n+--+\n", "bob").unwrap();
+        assert!(svg.contains("marker-end: url(#bob-arrow);"));
+        assert!(svg.contains(r#"<marker id="bob-arrow""#));
+        assert!(svg.contains(r#"class="bob-svgbob""#));
+    }
+}
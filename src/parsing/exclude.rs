@@ -0,0 +1,117 @@
+use orgize::Org;
+
+/// Tags that exclude a subtree when a file sets no `#+EXCLUDE_TAGS:`
+const DEFAULT_EXCLUDE_TAGS: [&str; 1] = ["noexport"];
+
+/// Detach subtrees that org export skips: headings carrying an exclude tag and `* COMMENT` headings
+/// Comment blocks and `#` lines never produce HTML, so only headlines need removing
+pub fn strip_excluded(org: &mut Org) {
+    let exclude_tags = exclude_tags(
+        org.keywords()
+            .filter(|keyword| keyword.key.eq_ignore_ascii_case("EXCLUDE_TAGS"))
+            .map(|keyword| keyword.value.as_ref()),
+    );
+
+    let excluded: Vec<_> = org
+        .headlines()
+        .filter(|headline| {
+            let title = headline.title(org);
+            title.is_commented() || title.tags.iter().any(|tag| exclude_tags.iter().any(|t| t == tag))
+        })
+        .collect();
+
+    for headline in excluded {
+        headline.detach(org);
+    }
+}
+
+/// Tags listed in `#+EXCLUDE_TAGS:` values, or the defaults when there are none
+fn exclude_tags<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let tags: Vec<String> = values
+        .flat_map(|value| value.split(|c: char| c.is_whitespace() || c == ':'))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    if tags.is_empty() {
+        DEFAULT_EXCLUDE_TAGS.iter().map(|tag| tag.to_string()).collect()
+    } else {
+        tags
+    }
+}
+
+/// Exclude tags of org source that has not been parsed yet
+pub fn source_exclude_tags(text: &str) -> Vec<String> {
+    exclude_tags(text.lines().filter_map(|line| {
+        let (key, value) = line.trim_start().strip_prefix("#+")?.split_once(':')?;
+        key.eq_ignore_ascii_case("EXCLUDE_TAGS").then_some(value)
+    }))
+}
+
+/// Level of a headline line in unparsed org source, `None` for other lines
+pub fn headline_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    let rest = &line[level..];
+    (level > 0 && (rest.trim().is_empty() || rest.starts_with([' ', '\t']))).then_some(level)
+}
+
+/// Whether a headline line in unparsed org source starts a subtree that `strip_excluded` removes
+pub fn is_excluded_headline(line: &str, exclude_tags: &[String]) -> bool {
+    let mut words = line.trim_start_matches('*').split_whitespace().peekable();
+    words.next_if(|word| matches!(*word, "TODO" | "DONE"));
+    let commented = words.peek() == Some(&"COMMENT");
+    let tagged = line
+        .split_whitespace()
+        .last()
+        .filter(|word| word.len() > 1 && word.starts_with(':') && word.ends_with(':'))
+        .is_some_and(|tags| tags.split(':').any(|tag| exclude_tags.iter().any(|t| t == tag)));
+    commented || tagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::SyntectHandler;
+    use pretty_assertions::assert_eq;
+
+    fn render(text: &str) -> (String, Vec<String>) {
+        let mut org = Org::parse(text);
+        strip_excluded(&mut org);
+        let mut handler = SyntectHandler::default();
+        let mut html = Vec::new();
+        org.write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let toc = handler.into_toc().into_iter().map(|item| item.text).collect();
+        (String::from_utf8(html).expect("Should be valid UTF-8"), toc)
+    }
+
+    #[test]
+    fn test_strips_noexport_and_comment_subtrees() {
+        let (html, toc) = render(
+            "* Intro\nPublic\n* Notes :noexport:\nSecret\n** Nested\nAlso secret\n* COMMENT Draft\nDraft text\n* Outro\n",
+        );
+        assert_eq!(toc, vec!["Intro", "Outro"]);
+        assert!(!html.contains("ecret"));
+        assert!(!html.contains("Draft"));
+    }
+
+    #[test]
+    fn test_strips_nested_excluded_heading_only() {
+        let (html, toc) = render("* Public\n** Private :noexport:\nHidden\n** Kept\n");
+        assert_eq!(toc, vec!["Public", "Kept"]);
+        assert!(!html.contains("Hidden"));
+    }
+
+    #[test]
+    fn test_exclude_tags_keyword_replaces_default() {
+        let (_, toc) = render("#+EXCLUDE_TAGS: private research\n* A :private:\n* B :noexport:\n* C :research:\n");
+        assert_eq!(toc, vec!["B"]);
+    }
+
+    #[test]
+    fn test_comment_blocks_are_not_rendered() {
+        let (html, _) = render("Visible\n#+begin_comment\nInternal note\n#+end_comment\n# line comment\n");
+        assert!(html.contains("Visible"));
+        assert!(!html.contains("Internal note"));
+        assert!(!html.contains("line comment"));
+    }
+}
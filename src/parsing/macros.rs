@@ -0,0 +1,387 @@
+use super::exclude::{headline_level, is_excluded_headline, source_exclude_tags};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// {{{name}}} or {{{name(arguments)}}}, arguments end at the first ")}}}"
+static MACRO_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\{([A-Za-z][-\w]*)(?:\((.*?)\))?\}\}\}")
+        .expect("MACRO_REGEX: hardcoded pattern is invalid")
+});
+
+// =verbatim= and ~code~ spans, also inside other markup such as *=bold verbatim=*, and
+// inline src_lang{code} or src_lang[headers]{code}, where macro calls are shown literally
+static VERBATIM_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:^|[\s\-(\[{'"*/_+])(?:=[^\s=](?:[^=]*?[^\s=])?=|~[^\s~](?:[^~]*?[^\s~])?~)|\bsrc_[^\s\[{]+(?:\[[^\]]*\])?\{[^}]*\}"#,
+    )
+    .expect("VERBATIM_REGEX: hardcoded pattern is invalid")
+});
+
+// Macros may expand to other macros, this stops a definition that refers to itself
+const MAX_DEPTH: usize = 16;
+
+// Blocks whose contents are never expanded
+const LITERAL_BLOCKS: [&str; 3] = ["src", "example", "export"];
+
+type Shortcode = fn(&[String]) -> Result<String>;
+
+/// Built-in macros implemented in Rust, expanding to raw HTML, and to plain text on keyword
+/// lines such as `#+TITLE:`, which are not rendered as HTML
+const SHORTCODES: [(&str, Shortcode, Shortcode); 3] = [
+    ("kbd", kbd, kbd_text),
+    ("youtube", youtube, youtube_text),
+    ("abbr", abbr, abbr_text),
+];
+
+/// Expand `{{{name(args)}}}` calls in org source before it is parsed
+/// Calls resolve to `#+MACRO:` definitions in the file, then the `title`, `date` and
+/// `keyword` macros, then the built-in shortcodes; anything else is an error
+/// Subtrees that `strip_excluded` removes are copied as written, they are never rendered
+/// Shortcodes on `#+KEY:` lines expand to plain text, the values end up in titles and attributes
+pub fn expand_macros(text: &str) -> Result<String> {
+    let context = MacroContext::collect(text);
+    let exclude_tags = source_exclude_tags(text);
+    let mut expanded = String::with_capacity(text.len());
+    let mut literal_block_end: Option<String> = None;
+    // Level of the excluded headline whose subtree the current line is in
+    let mut excluded_level: Option<usize> = None;
+
+    for (number, line) in text.split_inclusive('\n').enumerate() {
+        let directive = line.trim_start().to_lowercase();
+
+        if let Some(end) = &literal_block_end {
+            if directive.starts_with(end.as_str()) {
+                literal_block_end = None;
+            }
+            expanded.push_str(line);
+            continue;
+        }
+        if let Some(level) = headline_level(line) {
+            if excluded_level.is_some_and(|excluded| level <= excluded) {
+                excluded_level = None;
+            }
+            if excluded_level.is_none() && is_excluded_headline(line, &exclude_tags) {
+                excluded_level = Some(level);
+            }
+        }
+        if let Some(block) = LITERAL_BLOCKS
+            .iter()
+            .find(|block| directive.starts_with(&format!("#+begin_{}", block)))
+        {
+            literal_block_end = Some(format!("#+end_{}", block));
+            expanded.push_str(line);
+            continue;
+        }
+        // Definitions, fixed-width lines and excluded subtrees stay as written
+        if excluded_level.is_some()
+            || directive.starts_with("#+macro:") || directive.starts_with(": ") || directive.trim_end() == ":" {
+            expanded.push_str(line);
+            continue;
+        }
+
+        let line = context
+            .expand(line, 0, is_keyword_line(&directive))
+            .with_context(|| format!("Failed to expand macros on line {}", number + 1))?;
+        expanded.push_str(&line);
+    }
+
+    Ok(expanded)
+}
+
+struct MacroContext {
+    /// `#+MACRO: name body` definitions, names lowercased
+    definitions: HashMap<String, String>,
+    /// `#+KEY: value` lines, keys uppercased, for the `title`, `date` and `keyword` macros
+    keywords: HashMap<String, String>,
+}
+
+impl MacroContext {
+    fn collect(text: &str) -> Self {
+        let mut definitions = HashMap::new();
+        let mut keywords = HashMap::new();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.trim_start().strip_prefix("#+").and_then(|l| l.split_once(':')) else {
+                continue;
+            };
+            let value = value.trim();
+            if key.eq_ignore_ascii_case("MACRO") {
+                let (name, body) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+                definitions.insert(name.to_lowercase(), body.trim().to_string());
+            } else {
+                keywords.entry(key.to_uppercase()).or_insert_with(|| value.to_string());
+            }
+        }
+
+        Self { definitions, keywords }
+    }
+
+    fn expand(&self, line: &str, depth: usize, plain: bool) -> Result<String> {
+        if depth > MAX_DEPTH {
+            bail!("Macros nested more than {} levels deep, is a #+MACRO: defined in terms of itself?", MAX_DEPTH);
+        }
+
+        let verbatim: Vec<_> = VERBATIM_REGEX.find_iter(line).map(|m| m.range()).collect();
+        let mut expanded = String::with_capacity(line.len());
+        let mut last = 0;
+
+        for captures in MACRO_REGEX.captures_iter(line) {
+            let call = captures.get(0).expect("capture 0 is the whole match");
+            // Inline src bodies end at the first "}", which may be in the middle of a call
+            if verbatim.iter().any(|span| span.contains(&call.start())) {
+                continue;
+            }
+
+            let name = captures[1].to_lowercase();
+            let arguments = captures.get(2).map(|a| split_arguments(a.as_str())).unwrap_or_default();
+
+            expanded.push_str(&line[last..call.start()]);
+            expanded.push_str(&self.call(&name, &arguments, depth, plain)?);
+            last = call.end();
+        }
+
+        expanded.push_str(&line[last..]);
+        Ok(expanded)
+    }
+
+    fn call(&self, name: &str, arguments: &[String], depth: usize, plain: bool) -> Result<String> {
+        if let Some(body) = self.definitions.get(name) {
+            if body.starts_with("(eval") {
+                bail!("Macro '{}' uses (eval ...), which only Emacs can run", name);
+            }
+            return self.expand(&substitute_arguments(body, arguments), depth + 1, plain);
+        }
+
+        // Keyword values may call macros themselves, e.g. a title with a {{{kbd(...)}}}
+        let keyword = |key: &str| {
+            let value = self.keywords.get(&key.to_uppercase()).map(String::as_str).unwrap_or_default();
+            self.expand(value, depth + 1, plain)
+        };
+        match name {
+            "title" => return keyword("TITLE"),
+            "date" => return keyword("DATE"),
+            "keyword" => return keyword(arguments.first().map(String::as_str).unwrap_or_default()),
+            _ => {}
+        }
+
+        match SHORTCODES.iter().find(|(shortcode, ..)| *shortcode == name) {
+            Some((_, render, text)) => {
+                let html = render(arguments)?;
+                if plain {
+                    return text(arguments);
+                }
+                // Raw HTML goes through an export snippet so orgize passes it along untouched,
+                // an "@@" from the arguments would end the snippet early
+                Ok(format!("@@html:{}@@", html.replace('@', "&#64;")))
+            }
+            None => bail!(
+                "Unknown macro '{}' (define it with #+MACRO: or use one of: title, date, keyword, {})",
+                name,
+                SHORTCODES.iter().map(|(name, ..)| *name).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// `#+KEY: value` lines other than block delimiters, `directive` lowercased and trimmed at the start
+fn is_keyword_line(directive: &str) -> bool {
+    directive
+        .strip_prefix("#+")
+        .and_then(|rest| rest.split_once(':'))
+        .is_some_and(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace))
+}
+
+/// Split macro arguments on commas, `\,` is a literal comma
+fn split_arguments(arguments: &str) -> Vec<String> {
+    let mut split = vec![String::new()];
+    let mut chars = arguments.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&',') => {
+                split.last_mut().expect("split starts non-empty").push(',');
+                chars.next();
+            }
+            ',' => split.push(String::new()),
+            _ => split.last_mut().expect("split starts non-empty").push(c),
+        }
+    }
+    split.into_iter().map(|argument| argument.trim().to_string()).collect()
+}
+
+/// Replace `$1`, `$2`... in a macro body, missing arguments become empty
+fn substitute_arguments(body: &str, arguments: &[String]) -> String {
+    let mut substituted = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' || !chars.peek().is_some_and(char::is_ascii_digit) {
+            substituted.push(c);
+            continue;
+        }
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        let argument = digits
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|index| arguments.get(index));
+        if let Some(argument) = argument {
+            substituted.push_str(argument);
+        }
+    }
+    substituted
+}
+
+/// `{{{kbd(C-x C-f)}}}` - a key sequence, one `<kbd>` per key
+fn kbd(arguments: &[String]) -> Result<String> {
+    let sequence = arguments.join(",");
+    let keys: Vec<String> = sequence
+        .split_whitespace()
+        .map(|key| format!("<kbd>{}</kbd>", html_escape::encode_text(key)))
+        .collect();
+    if keys.is_empty() {
+        bail!("kbd needs a key sequence, e.g. {{{{{{kbd(C-x C-f)}}}}}}");
+    }
+    Ok(format!("<kbd class=\"keys\">{}</kbd>", keys.join(" ")))
+}
+
+fn kbd_text(arguments: &[String]) -> Result<String> {
+    Ok(arguments.join(",").split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// `{{{youtube(id, title)}}}` - a placeholder that only contacts YouTube once clicked
+fn youtube(arguments: &[String]) -> Result<String> {
+    let id = arguments.first().map(String::as_str).unwrap_or_default();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("youtube needs a video id such as dQw4w9WgXcQ, got '{}'", id);
+    }
+    let title = html_escape::encode_double_quoted_attribute(
+        arguments.get(1).filter(|title| !title.is_empty()).map(String::as_str).unwrap_or("YouTube video"),
+    )
+    .into_owned();
+
+    Ok(format!(
+        "<span class=\"video-embed\" data-youtube-id=\"{id}\" data-title=\"{title}\">\
+         <span class=\"video-embed-title\">{title}</span>\
+         <button type=\"button\" class=\"video-embed-load\">Load video from YouTube</button> \
+         <a href=\"https://www.youtube.com/watch?v={id}\">Watch on YouTube</a></span>"
+    ))
+}
+
+fn youtube_text(arguments: &[String]) -> Result<String> {
+    Ok(arguments.get(1).filter(|title| !title.is_empty()).cloned().unwrap_or_else(|| "YouTube video".to_string()))
+}
+
+/// `{{{abbr(TLA, three-letter acronym)}}}` - an abbreviation with its expansion on hover
+fn abbr(arguments: &[String]) -> Result<String> {
+    match arguments {
+        [abbreviation, meaning @ ..] if !abbreviation.is_empty() && !meaning.is_empty() => Ok(format!(
+            "<abbr title=\"{}\">{}</abbr>",
+            html_escape::encode_double_quoted_attribute(&meaning.join(", ")),
+            html_escape::encode_text(abbreviation)
+        )),
+        _ => bail!("abbr needs an abbreviation and its meaning, e.g. {{{{{{abbr(TLA, three-letter acronym)}}}}}}"),
+    }
+}
+
+fn abbr_text(arguments: &[String]) -> Result<String> {
+    Ok(arguments.first().cloned().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_user_macros_with_arguments() {
+        let text = "#+MACRO: greet Hello, $1 and $2!\n{{{greet(Alice, Bob\\, Jr)}}}\n";
+        assert_eq!(
+            expand_macros(text).unwrap(),
+            "#+MACRO: greet Hello, $1 and $2!\nHello, Alice and Bob, Jr!\n"
+        );
+    }
+
+    #[test]
+    fn test_keyword_macros() {
+        let text = "#+TITLE: My Post\n#+SUBTITLE: More\nIn {{{title}}}: {{{keyword(subtitle)}}}\n";
+        assert!(expand_macros(text).unwrap().ends_with("In My Post: More\n"));
+    }
+
+    #[test]
+    fn test_shortcodes() {
+        let expanded = expand_macros("{{{kbd(C-x C-f)}}} {{{abbr(TLA, three, letters)}}}").unwrap();
+        assert_eq!(
+            expanded,
+            "@@html:<kbd class=\"keys\"><kbd>C-x</kbd> <kbd>C-f</kbd></kbd>@@ \
+             @@html:<abbr title=\"three, letters\">TLA</abbr>@@"
+        );
+
+        let video = expand_macros("{{{youtube(dQw4w9WgXcQ, Demo)}}}").unwrap();
+        assert!(video.contains("data-youtube-id=\"dQw4w9WgXcQ\""));
+        assert!(!video.contains("<iframe"));
+        assert!(expand_macros("{{{youtube(\"><script>)}}}").is_err());
+    }
+
+    #[test]
+    fn test_code_is_left_alone() {
+        let text = "Write ~{{{kbd(C-x)}}}~ or ={{{x}}}=\n#+begin_src org\n{{{nope}}}\n#+end_src\n: {{{nope}}}\n";
+        assert_eq!(expand_macros(text).unwrap(), text);
+    }
+
+    #[test]
+    fn test_keyword_lines_expand_to_plain_text() {
+        let text = "#+TITLE: Press {{{kbd(C-x C-f)}}}\n#+DESCRIPTION: About {{{abbr(TLA, three letters)}}}\n\
+                    #+CAPTION: {{{youtube(dQw4w9WgXcQ, Demo)}}}\nSee {{{title}}}\n";
+        assert_eq!(
+            expand_macros(text).unwrap(),
+            "#+TITLE: Press C-x C-f\n#+DESCRIPTION: About TLA\n#+CAPTION: Demo\n\
+             See Press @@html:<kbd class=\"keys\"><kbd>C-x</kbd> <kbd>C-f</kbd></kbd>@@\n"
+        );
+        assert!(expand_macros("#+TITLE: {{{youtube(\"><script>)}}}\n").is_err(), "Arguments are still checked");
+    }
+
+    #[test]
+    fn test_inline_src_and_nested_verbatim_are_left_alone() {
+        let text = "Run src_sh{echo {{{kbd(x)}}}} or src_sh[:exports code]{ls {{{x}}}}\n\
+                    *={{{kbd(x)}}}=* /~{{{x}}}~/ [[https://example.com][={{{x}}}=]] a-={{{x}}}=\n";
+        assert_eq!(expand_macros(text).unwrap(), text);
+    }
+
+    #[test]
+    fn test_at_signs_in_shortcode_arguments_cannot_end_the_snippet() {
+        assert_eq!(
+            expand_macros("{{{abbr(a@@b, x@@html:<script>@@)}}} {{{kbd(C-@)}}}").unwrap(),
+            "@@html:<abbr title=\"x&#64;&#64;html:&lt;script&gt;&#64;&#64;\">a&#64;&#64;b</abbr>@@ \
+             @@html:<kbd class=\"keys\"><kbd>C-&#64;</kbd></kbd>@@"
+        );
+    }
+
+    #[test]
+    fn test_unknown_macro_is_an_error() {
+        let error = expand_macros("Fine\n{{{nope(1)}}}\n").unwrap_err();
+        assert_eq!(error.to_string(), "Failed to expand macros on line 2");
+        assert!(format!("{:#}", error).contains("Unknown macro 'nope'"));
+    }
+
+    #[test]
+    fn test_excluded_subtrees_are_not_expanded() {
+        let text = "* Draft :noexport:\n{{{nope}}}\n** Deeper\n{{{nope}}}\n\
+                    * COMMENT Notes\n{{{nope}}}\n* Published\n{{{title}}}\n";
+        assert_eq!(
+            expand_macros(text).unwrap(),
+            "* Draft :noexport:\n{{{nope}}}\n** Deeper\n{{{nope}}}\n* COMMENT Notes\n{{{nope}}}\n* Published\n\n"
+        );
+
+        let text = "#+EXCLUDE_TAGS: private\n* Mine :private:\n{{{nope}}}\n* Draft :noexport:\n{{{nope}}}\n";
+        assert!(expand_macros(text).is_err(), "Custom EXCLUDE_TAGS replace noexport");
+    }
+
+    #[test]
+    fn test_recursive_macro_is_an_error() {
+        assert!(expand_macros("#+MACRO: loop {{{loop}}}\n{{{loop}}}\n").is_err());
+    }
+}
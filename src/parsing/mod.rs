@@ -1,5 +1,25 @@
+mod anchors;
+mod bibtex;
+mod callouts;
+mod charts;
+mod citations;
+mod diagrams;
+mod exclude;
+mod macros;
+mod navigation;
+mod options;
 mod parser;
+mod raw_html;
+mod series;
+mod src_block;
+mod summary;
 mod syntect_handler;
+mod text;
+mod themes;
+mod typography;
 
+pub use navigation::build_navigation;
 pub use parser::{parse_about, parse_posts};
+pub use series::collect_series;
 pub use syntect_handler::SyntectHandler;
+pub use themes::{highlight_css, load_theme};
@@ -0,0 +1,214 @@
+use crate::models::{Post, PostLink, PostNavigation};
+use crate::utils::plain_text;
+use std::collections::{HashMap, HashSet};
+
+/// How many related posts each post lists
+const RELATED_POSTS: usize = 3;
+
+/// How much a full tag match counts next to identical text
+const TAG_WEIGHT: f64 = 0.5;
+
+/// Common English words that say nothing about what a post is about
+const STOP_WORDS: [&str; 48] = [
+    "about", "after", "all", "also", "and", "any", "are", "because", "been", "but", "can", "could", "did", "does",
+    "for", "from", "had", "has", "have", "how", "into", "its", "just", "more", "not", "now", "only", "other",
+    "our", "out", "than", "that", "the", "then", "there", "these", "they", "this", "was", "were", "what", "when",
+    "which", "will", "with", "would", "you", "your",
+];
+
+/// Chronological previous/next links and related posts for every listed post
+/// Related posts are ranked by TF-IDF cosine similarity of their prose plus shared `#+FILETAGS:`
+pub fn build_navigation(posts: &HashMap<String, Post>) -> HashMap<String, PostNavigation> {
+    // Unlisted posts neither link to nor are linked from listed ones
+    let mut ordered: Vec<&Post> = posts.values().filter(|post| post.is_listed()).collect();
+    ordered.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.slug.cmp(&b.slug)));
+
+    let vectors = tf_idf(&ordered);
+    ordered
+        .iter()
+        .enumerate()
+        .map(|(index, post)| {
+            let mut scored: Vec<(f64, &Post)> = ordered
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(other, candidate)| {
+                    let score = cosine(&vectors[index], &vectors[other]) + TAG_WEIGHT * tag_overlap(post, candidate);
+                    (score, *candidate)
+                })
+                .filter(|(score, _)| *score > 0.0)
+                .collect();
+            // Best match first, newer posts break ties
+            scored.sort_by(|(a, a_post), (b, b_post)| {
+                b.total_cmp(a)
+                    .then_with(|| b_post.date.cmp(&a_post.date))
+                    .then_with(|| a_post.slug.cmp(&b_post.slug))
+            });
+
+            let navigation = PostNavigation {
+                previous: index.checked_sub(1).map(|previous| link(ordered[previous])),
+                next: ordered.get(index + 1).map(|next| link(next)),
+                related: scored.into_iter().take(RELATED_POSTS).map(|(_, post)| link(post)).collect(),
+            };
+            (post.slug.clone(), navigation)
+        })
+        .collect()
+}
+
+fn link(post: &Post) -> PostLink {
+    PostLink {
+        slug: post.slug.clone(),
+        title: post.title.clone(),
+    }
+}
+
+/// Words of three or more letters, lowercased, without stop words and numbers
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3 && !word.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+/// A unit-length TF-IDF vector per post, in the order given
+fn tf_idf(posts: &[&Post]) -> Vec<HashMap<String, f64>> {
+    let counts: Vec<HashMap<String, usize>> = posts
+        .iter()
+        .map(|post| {
+            let mut counts = HashMap::new();
+            for term in terms(&plain_text(&post.content)) {
+                *counts.entry(term).or_insert(0) += 1;
+            }
+            counts
+        })
+        .collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for counts in &counts {
+        for term in counts.keys() {
+            *document_frequency.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let total = posts.len() as f64;
+    counts
+        .iter()
+        .map(|counts| {
+            let length = counts.values().sum::<usize>().max(1) as f64;
+            let mut vector: HashMap<String, f64> = counts
+                .iter()
+                .map(|(term, count)| {
+                    let idf = (total / document_frequency[term.as_str()] as f64).ln();
+                    (term.clone(), *count as f64 / length * idf)
+                })
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect();
+            let norm = vector.values().map(|weight| weight * weight).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                vector.values_mut().for_each(|weight| *weight /= norm);
+            }
+            vector
+        })
+        .collect()
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let (smaller, larger) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    smaller
+        .iter()
+        .filter_map(|(term, weight)| larger.get(term).map(|other| weight * other))
+        .sum()
+}
+
+/// Shared tags over all tags of the two posts
+fn tag_overlap(a: &Post, b: &Post) -> f64 {
+    let a_tags: HashSet<&String> = a.tags.iter().collect();
+    let b_tags: HashSet<&String> = b.tags.iter().collect();
+    let union = a_tags.union(&b_tags).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a_tags.intersection(&b_tags).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn post(slug: &str, day: u32, content: &str, tags: &[&str]) -> (String, Post) {
+        let post = Post {
+            slug: slug.to_string(),
+            title: slug.to_uppercase(),
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            content: format!("<p>{}</p>", content),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        (slug.to_string(), post)
+    }
+
+    fn slugs(links: &[PostLink]) -> Vec<&str> {
+        links.iter().map(|link| link.slug.as_str()).collect()
+    }
+
+    #[test]
+    fn test_previous_and_next_follow_dates() {
+        let posts = HashMap::from([
+            post("second", 2, "", &[]),
+            post("first", 1, "", &[]),
+            post("third", 3, "", &[]),
+        ]);
+        let navigation = build_navigation(&posts);
+
+        assert_eq!(navigation["first"].previous, None);
+        assert_eq!(navigation["first"].next.as_ref().map(|link| link.slug.as_str()), Some("second"));
+        assert_eq!(navigation["second"].previous.as_ref().map(|link| link.slug.as_str()), Some("first"));
+        assert_eq!(navigation["third"].next, None);
+    }
+
+    #[test]
+    fn test_related_by_text_and_tags() {
+        let posts = HashMap::from([
+            post("flakes", 1, "Nix flakes pin nixpkgs for reproducible builds", &["nix"]),
+            post("direnv", 2, "Direnv loads the nix flake shell with reproducible nixpkgs", &["nix"]),
+            post("axum", 3, "Routing requests in axum with tower middleware", &["rust"]),
+            post("askama", 4, "Askama templates render axum responses", &["rust", "web"]),
+            post("cooking", 5, "Bread dough rises overnight", &[]),
+        ]);
+        let navigation = build_navigation(&posts);
+
+        assert_eq!(slugs(&navigation["flakes"].related), vec!["direnv"]);
+        assert_eq!(slugs(&navigation["axum"].related), vec!["askama"]);
+        assert!(navigation["cooking"].related.is_empty());
+    }
+
+    #[test]
+    fn test_unlisted_posts_are_skipped() {
+        let (_, mut unlisted) = post("unlisted", 2, "Nix flakes", &["nix"]);
+        unlisted.visibility = crate::models::Visibility::Unlisted;
+        let posts = HashMap::from([
+            post("first", 1, "Nix flakes", &["nix"]),
+            ("unlisted".to_string(), unlisted),
+            post("third", 3, "Nix flakes", &["nix"]),
+        ]);
+        let navigation = build_navigation(&posts);
+
+        assert!(!navigation.contains_key("unlisted"));
+        assert_eq!(navigation["first"].next.as_ref().map(|link| link.slug.as_str()), Some("third"));
+        assert_eq!(slugs(&navigation["first"].related), vec!["third"]);
+    }
+
+    #[test]
+    fn test_code_does_not_count() {
+        let (_, mut with_code) = post("code", 1, "Bread", &[]);
+        with_code.content.push_str("<pre>nixpkgs nixpkgs nixpkgs</pre>");
+        let posts = HashMap::from([
+            ("code".to_string(), with_code),
+            post("nix", 2, "nixpkgs", &[]),
+            post("bread", 3, "Bread", &[]),
+        ]);
+        assert_eq!(slugs(&build_navigation(&posts)["nix"].related), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,140 @@
+use orgize::Org;
+
+/// How `^` and `_` in text are exported, from the `^:` option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubSuperscripts {
+    /// `^:nil` - always literal
+    Off,
+    /// `^:{}` - only `a^{b}` and `a_{b}`
+    Braced,
+    /// `^:t` - also `a^b` and `a_b`
+    On,
+}
+
+/// The subset of `#+OPTIONS:` the blog honors
+/// Site-wide defaults come from `[export] options` in site.toml, each file's `#+OPTIONS:` override them
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    /// Deepest heading level listed in the TOC (`toc:N`), `None` for `toc:nil`
+    pub toc: Option<usize>,
+    /// Deepest heading level numbered (`num:N`), `None` for `num:nil`
+    pub num: Option<usize>,
+    pub sub_superscripts: SubSuperscripts,
+    /// Deepest level exported as a heading (`H:N`), deeper headlines become paragraphs
+    pub headline_levels: usize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            toc: Some(usize::MAX),
+            num: None,
+            // Existing posts render `a_b` and `a^{b}` literally unless site.toml opts in
+            sub_superscripts: SubSuperscripts::Off,
+            headline_levels: 6,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Site defaults followed by the file's own `#+OPTIONS:` lines
+    pub fn for_file(site_options: &str, org: &Org) -> Self {
+        let mut options = Self::default();
+        options.apply(site_options);
+        for keyword in org.keywords() {
+            if keyword.key.eq_ignore_ascii_case("OPTIONS") {
+                options.apply(&keyword.value);
+            }
+        }
+        options
+    }
+
+    /// Apply `key:value` pairs, ignoring options the blog doesn't use and malformed values
+    pub fn apply(&mut self, options: &str) {
+        for option in options.split_whitespace() {
+            let Some((key, value)) = option.split_once(':') else {
+                continue;
+            };
+            match key {
+                "toc" => self.toc = parse_depth(value).unwrap_or(self.toc),
+                "num" => self.num = parse_depth(value).unwrap_or(self.num),
+                "^" => {
+                    self.sub_superscripts = match value {
+                        "nil" => SubSuperscripts::Off,
+                        "{}" => SubSuperscripts::Braced,
+                        "t" => SubSuperscripts::On,
+                        _ => self.sub_superscripts,
+                    }
+                }
+                "H" => {
+                    if let Ok(levels) = value.parse::<usize>() {
+                        self.headline_levels = levels;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn is_heading(&self, level: usize) -> bool {
+        level <= self.headline_levels
+    }
+
+    pub fn in_toc(&self, level: usize) -> bool {
+        self.is_heading(level) && self.toc.is_some_and(|depth| level <= depth)
+    }
+
+    pub fn is_numbered(&self, level: usize) -> bool {
+        self.is_heading(level) && self.num.is_some_and(|depth| level <= depth)
+    }
+}
+
+/// `t` is unlimited (capped by `H:` later), `nil` is off, a number is a level limit
+fn parse_depth(value: &str) -> Option<Option<usize>> {
+    match value {
+        "t" => Some(Some(usize::MAX)),
+        "nil" => Some(None),
+        _ => value.parse::<usize>().ok().map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_apply_options() {
+        let mut options = ExportOptions::default();
+        options.apply("toc:2 num:t ^:nil H:4 author:nil broken");
+        assert_eq!(
+            options,
+            ExportOptions {
+                toc: Some(2),
+                num: Some(usize::MAX),
+                sub_superscripts: SubSuperscripts::Off,
+                headline_levels: 4,
+            }
+        );
+        assert!(options.in_toc(2));
+        assert!(!options.in_toc(3));
+        assert!(options.is_numbered(4));
+        assert!(!options.is_numbered(5));
+    }
+
+    #[test]
+    fn test_file_options_override_site_defaults() {
+        let org = Org::parse("#+OPTIONS: toc:nil\n#+OPTIONS: ^:t\n* Heading\n");
+        let options = ExportOptions::for_file("toc:1 num:1", &org);
+        assert_eq!(options.toc, None);
+        assert_eq!(options.num, Some(1));
+        assert_eq!(options.sub_superscripts, SubSuperscripts::On);
+    }
+
+    #[test]
+    fn test_sub_superscripts_are_literal_by_default() {
+        let org = Org::parse("H_{2}O\n");
+        assert_eq!(ExportOptions::for_file("", &org).sub_superscripts, SubSuperscripts::Off);
+        assert_eq!(ExportOptions::for_file("^:{}", &org).sub_superscripts, SubSuperscripts::Braced);
+    }
+}
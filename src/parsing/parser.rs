@@ -1,10 +1,20 @@
-use crate::models::Post;
-use super::SyntectHandler;
-use anyhow::{Context, Result};
+use crate::models::{Author, Post, PostStats, RawHtmlPolicy, SeriesInfo, SiteConfig, Visibility};
+use crate::utils::{fold_typography, prose_word_count, slugify};
+use super::anchors::HeadingAnchors;
+use super::bibtex::Bibliography;
+use super::citations::Citations;
+use super::exclude::strip_excluded;
+use super::macros::expand_macros;
+use super::options::ExportOptions;
+use super::summary::{normalize_more_marker, split_summary};
+use super::{load_theme, SyntectHandler};
+use anyhow::{bail, Context, Result};
+use argon2::PasswordHash;
 use chrono::NaiveDate;
 use orgize::{Element, Event, Org};
 use rust_embed::RustEmbed;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(RustEmbed)]
 #[folder = "content/posts/"]
@@ -17,9 +27,13 @@ struct AboutContent;
 // Include generated metadata from build.rs
 include!(concat!(env!("OUT_DIR"), "/generated_metadata.rs"));
 
-pub fn parse_posts() -> Result<HashMap<String, Post>> {
+pub fn parse_posts(config: &SiteConfig) -> Result<HashMap<String, Post>> {
     let mut posts = HashMap::new();
 
+    let feed_theme = load_theme(config.highlight.feed_theme())
+        .context("Failed to load feed highlight theme")?;
+    let bibliography = load_bibliography()?;
+
     for filename in Posts::iter() {
         let filename_str = filename.as_ref();
         if !filename_str.ends_with(".org") {
@@ -31,14 +45,32 @@ pub fn parse_posts() -> Result<HashMap<String, Post>> {
             .with_context(|| format!("Failed to load embedded post file: {}", filename_str))?;
         let text = std::str::from_utf8(content.data.as_ref())
             .context("Post file contains invalid UTF-8")?;
+        let text = expand_macros(text)
+            .with_context(|| format!("Failed to expand macros in post: {}", filename_str))?;
+        let text = normalize_more_marker(&text);
 
-        let org = Org::parse(text);
+        let mut org = Org::parse(&text);
+        // Drafting notes must not reach the HTML, TOC or search index
+        strip_excluded(&mut org);
 
         let mut title = String::new();
         let mut date = None;
         let mut description = String::new();
+        let mut series_name = None;
+        let mut series_part = None;
+        let mut tags = Vec::new();
+        let mut code_blocks = 0;
+        let mut authors = Vec::new();
+        let mut visibility = Visibility::Public;
+        let mut password_hash = None;
+        let mut canonical_url = None;
+        let mut image = None;
+        let mut robots = Vec::new();
 
         for event in org.iter() {
+            if let Event::Start(Element::SourceBlock(_)) = event {
+                code_blocks += 1;
+            }
             if let Event::Start(Element::Keyword(keyword)) = event {
                 match keyword.key.to_uppercase().as_str() {
                     "TITLE" => title = keyword.value.to_string(),
@@ -46,19 +78,106 @@ pub fn parse_posts() -> Result<HashMap<String, Post>> {
                         date = NaiveDate::parse_from_str(&keyword.value, "%Y-%m-%d").ok();
                     }
                     "DESCRIPTION" => description = keyword.value.to_string(),
+                    // `:rust:nix:` as in Emacs, or space separated
+                    "FILETAGS" => tags.extend(
+                        keyword
+                            .value
+                            .split(|c: char| c == ':' || c.is_whitespace())
+                            .filter(|tag| !tag.is_empty())
+                            .map(str::to_lowercase),
+                    ),
+                    // Repeated or comma separated for several authors
+                    "AUTHOR" => authors.extend(parse_authors(config, filename_str, &keyword.value)),
+                    "VISIBILITY" => {
+                        visibility = match keyword.value.trim().to_lowercase().as_str() {
+                            "public" => Visibility::Public,
+                            "unlisted" => Visibility::Unlisted,
+                            other => bail!(
+                                "Invalid #+VISIBILITY: '{}' in post: {} (expected public or unlisted)",
+                                other,
+                                filename_str
+                            ),
+                        }
+                    }
+                    "PASSWORD_HASH" => {
+                        let hash = keyword.value.trim();
+                        let is_argon2 = PasswordHash::new(hash).is_ok_and(|hash| hash.algorithm.as_str().starts_with("argon2"));
+                        if !is_argon2 {
+                            bail!(
+                                "Invalid #+PASSWORD_HASH: in post: {} (expected an argon2 hash such as $argon2id$v=19$...)",
+                                filename_str
+                            );
+                        }
+                        password_hash = Some(hash.to_string());
+                    }
+                    "CANONICAL_URL" => {
+                        let url = keyword.value.trim();
+                        if !url.starts_with("https://") && !url.starts_with("http://") {
+                            bail!("Invalid #+CANONICAL_URL: '{}' in post: {} (expected an absolute http(s) URL)", url, filename_str);
+                        }
+                        canonical_url = Some(url.to_string());
+                    }
+                    "IMAGE" => {
+                        let url = keyword.value.trim();
+                        if !url.starts_with('/') && !url.starts_with("https://") && !url.starts_with("http://") {
+                            bail!("Invalid #+IMAGE: '{}' in post: {} (expected a /path or an absolute http(s) URL)", url, filename_str);
+                        }
+                        image = Some(url.to_string());
+                    }
+                    // `noindex, nofollow` as in a robots meta tag, commas optional
+                    "ROBOTS" => robots.extend(
+                        parse_robots(&keyword.value).with_context(|| format!("Invalid #+ROBOTS: in post: {}", filename_str))?,
+                    ),
+                    "SERIES" => series_name = Some(keyword.value.trim().to_string()),
+                    "SERIES_PART" => {
+                        let part = keyword.value.trim().parse::<usize>().with_context(|| {
+                            format!("Invalid #+SERIES_PART: '{}' in post: {}", keyword.value, filename_str)
+                        })?;
+                        series_part = Some(part);
+                    }
                     _ => {}
                 }
             }
         }
 
-        let mut handler = SyntectHandler::default();
+        if series_part.is_some() && series_name.is_none() {
+            eprintln!("Warning: {} has #+SERIES_PART: but no #+SERIES:, ignoring it", filename_str);
+        }
+        let series = series_name.filter(|name| !name.is_empty()).map(|name| SeriesInfo {
+            slug: slugify(&name),
+            name,
+            part: series_part,
+        });
+
+        let options = ExportOptions::for_file(&config.export.options, &org);
+        let mut handler = SyntectHandler::new(config)
+            .with_options(options.clone())
+            .with_anchors(HeadingAnchors::for_org(&org))
+            .with_citations(Citations::new(bibliography.clone(), config.citations.style))
+            .with_diagram_prefix(format!("{}-diagram", slug))
+            .with_summary_markers();
         let mut html_bytes = Vec::new();
         org.write_html_custom(&mut html_bytes, &mut handler)
             .context("Failed to generate HTML from org-mode content")?;
         let html = String::from_utf8(html_bytes)
             .context("Generated HTML contains invalid UTF-8")?;
+        let (html, summary_html) = split_summary(&html);
+        check_citations(filename_str, handler.unknown_citations())?;
+        check_raw_html(filename_str, config.raw_html.policy, handler.removed_raw_html())?;
+        warn_fallback_languages(filename_str, handler.fallback_languages());
+        warn_unresolved_links(filename_str, handler.unresolved_links());
         let toc = handler.into_toc();
 
+        let mut feed_handler = SyntectHandler::inline_styled(config, feed_theme.clone())
+            .with_options(options)
+            .with_anchors(HeadingAnchors::for_org(&org))
+            .with_citations(Citations::new(bibliography.clone(), config.citations.style));
+        let mut feed_bytes = Vec::new();
+        org.write_html_custom(&mut feed_bytes, &mut feed_handler)
+            .context("Failed to generate feed HTML from org-mode content")?;
+        let feed_content = String::from_utf8(feed_bytes)
+            .context("Generated feed HTML contains invalid UTF-8")?;
+
         // Safe unwrap: 1970-01-01 is a valid date
         let pub_date = date.unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
 
@@ -74,6 +193,8 @@ pub fn parse_posts() -> Result<HashMap<String, Post>> {
             })
             .unwrap_or_default();
 
+        let stats = PostStats::new(prose_word_count(&html), code_blocks);
+
         posts.insert(
             slug.clone(),
             Post {
@@ -83,9 +204,20 @@ pub fn parse_posts() -> Result<HashMap<String, Post>> {
                 date: pub_date,
                 updated,
                 description,
-                content_lower: html.to_lowercase(),
+                content_lower: fold_typography(&html).to_lowercase(),
                 content: html,
+                summary_html,
+                feed_content,
                 toc,
+                series,
+                authors,
+                tags,
+                stats,
+                visibility,
+                password_hash,
+                canonical_url,
+                image,
+                robots,
             },
         );
     }
@@ -93,20 +225,197 @@ pub fn parse_posts() -> Result<HashMap<String, Post>> {
     Ok(posts)
 }
 
-pub fn parse_about() -> Result<String> {
+pub fn parse_about(config: &SiteConfig) -> Result<String> {
     let content = AboutContent::get("about.org")
         .context("Failed to load embedded about.org file")?;
     let text = std::str::from_utf8(content.data.as_ref())
         .context("about.org file contains invalid UTF-8")?;
+    let text = expand_macros(text).context("Failed to expand macros in about.org")?;
 
-    let org = Org::parse(text);
+    let mut org = Org::parse(&text);
+    strip_excluded(&mut org);
 
-    let mut handler = SyntectHandler::default();
+    let options = ExportOptions::for_file(&config.export.options, &org);
+    let mut handler = SyntectHandler::new(config)
+        .with_options(options)
+        .with_anchors(HeadingAnchors::for_org(&org))
+        .with_citations(Citations::new(load_bibliography()?, config.citations.style));
     let mut html_bytes = Vec::new();
     org.write_html_custom(&mut html_bytes, &mut handler)
         .context("Failed to generate HTML from about.org content")?;
     let html = String::from_utf8(html_bytes)
         .context("Generated HTML contains invalid UTF-8")?;
+    check_citations("about.org", handler.unknown_citations())?;
+    check_raw_html("about.org", config.raw_html.policy, handler.removed_raw_html())?;
+    warn_fallback_languages("about.org", handler.fallback_languages());
+    warn_unresolved_links("about.org", handler.unresolved_links());
 
     Ok(html)
 }
+
+/// Robots meta directives a post may set
+const ROBOTS_DIRECTIVES: [&str; 11] = [
+    "all", "index", "follow", "noindex", "nofollow", "none", "noarchive", "nosnippet", "noimageindex", "notranslate",
+    "indexifembedded",
+];
+
+/// Directives that take a value, e.g. `max-snippet:50`
+const ROBOTS_SETTINGS: [&str; 3] = ["max-snippet", "max-image-preview", "max-video-preview"];
+
+/// `noindex, nofollow` as in a robots meta tag, commas optional, lowercased
+fn parse_robots(value: &str) -> Result<Vec<String>> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            let directive = directive.to_lowercase();
+            let known = match directive.split_once(':') {
+                Some((setting, value)) => ROBOTS_SETTINGS.contains(&setting) && !value.is_empty(),
+                None => ROBOTS_DIRECTIVES.contains(&directive.as_str()),
+            };
+            if !known {
+                bail!("Unknown robots directive '{}'", directive);
+            }
+            Ok(directive)
+        })
+        .collect()
+}
+
+/// Authors of one `#+AUTHOR:` line, separated by commas
+fn parse_authors(config: &SiteConfig, filename: &str, value: &str) -> Vec<Author> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .map(|author| resolve_author(config, filename, author))
+        .collect()
+}
+
+/// Match an `#+AUTHOR:` entry against `[authors]`, keeping unknown names as a plain byline
+fn resolve_author(config: &SiteConfig, filename: &str, author: &str) -> Author {
+    match config.author_id(author) {
+        Some(id) => Author {
+            id: Some(id.to_string()),
+            name: config.authors[id].name.clone(),
+        },
+        None => {
+            if !config.authors.is_empty() {
+                eprintln!(
+                    "Warning: {} has #+AUTHOR: {}, which matches no [authors.<id>] in site.toml",
+                    filename, author
+                );
+            }
+            Author {
+                id: None,
+                name: author.to_string(),
+            }
+        }
+    }
+}
+
+/// The embedded `content/references.bib`, empty when there is none
+fn load_bibliography() -> Result<Arc<Bibliography>> {
+    let Some(content) = AboutContent::get("references.bib") else {
+        return Ok(Arc::default());
+    };
+    let text = std::str::from_utf8(content.data.as_ref())
+        .context("references.bib file contains invalid UTF-8")?;
+    let bibliography = Bibliography::parse(text).context("Failed to parse content/references.bib")?;
+    Ok(Arc::new(bibliography))
+}
+
+fn check_citations(filename: &str, unknown_keys: &[String]) -> Result<()> {
+    if !unknown_keys.is_empty() {
+        bail!(
+            "{} cites keys that are not in content/references.bib: {}",
+            filename,
+            unknown_keys.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Fail on HTML the policy rejects, or report what sanitizing removed
+fn check_raw_html(filename: &str, policy: RawHtmlPolicy, removed: &[String]) -> Result<()> {
+    if removed.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        RawHtmlPolicy::Reject => bail!(
+            "{} contains raw HTML outside the allowlist ({}). Remove it, or set [raw_html] policy in site.toml",
+            filename,
+            removed.join(", ")
+        ),
+        _ => {
+            eprintln!(
+                "Warning: {} contains raw HTML outside the allowlist, removed: {}",
+                filename,
+                removed.join(", ")
+            );
+            Ok(())
+        }
+    }
+}
+
+fn warn_fallback_languages(filename: &str, languages: &[String]) {
+    if !languages.is_empty() {
+        eprintln!(
+            "Warning: {} uses languages without a syntax definition ({}), rendered as plain text. \
+             Add a .sublime-syntax file to syntaxes/ or an alias under [highlight.aliases] in site.toml",
+            filename,
+            languages.join(", ")
+        );
+    }
+}
+
+fn warn_unresolved_links(filename: &str, links: &[String]) {
+    if !links.is_empty() {
+        eprintln!(
+            "Warning: {} has internal links that match no heading ({}). \
+             Use [[#custom-id]] with a :CUSTOM_ID: property or [[*Heading text]]",
+            filename,
+            links.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_robots() {
+        assert_eq!(parse_robots("noindex, NoFollow max-snippet:50").unwrap(), vec!["noindex", "nofollow", "max-snippet:50"]);
+        assert!(parse_robots("noindx").is_err());
+        assert!(parse_robots("max-snippet:").is_err());
+        assert!(parse_robots("nofollow, unknown:1").is_err());
+    }
+
+    #[test]
+    fn test_parse_authors_by_id_name_and_unknown() {
+        let config: SiteConfig = toml::from_str(
+            r#"
+            [site]
+            name = "Blog"
+            description = "A blog"
+
+            [authors.sean]
+            name = "Sean Wall"
+
+            [authors.ada]
+            name = "Ada Lovelace"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_authors(&config, "post.org", "sean, ada lovelace, Guest Writer,"),
+            vec![
+                Author { id: Some("sean".to_string()), name: "Sean Wall".to_string() },
+                Author { id: Some("ada".to_string()), name: "Ada Lovelace".to_string() },
+                Author { id: None, name: "Guest Writer".to_string() },
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::models::RawHtmlPolicy;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+/// Tags raw HTML may use under the `sanitize` and `reject` policies, covering the built-in shortcodes
+const ALLOWED_TAGS: [&str; 56] = [
+    "a", "abbr", "audio", "b", "blockquote", "br", "button", "caption", "cite", "code", "col", "colgroup", "dd",
+    "del", "details", "dfn", "div", "dl", "dt", "em", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6",
+    "hr", "i", "img", "ins", "kbd", "li", "mark", "ol", "p", "pre", "q", "s", "samp", "small", "source", "span",
+    "strong", "sub", "summary", "sup", "table", "tbody", "td", "th", "thead", "tr", "ul", "video",
+];
+
+/// Attributes allowed on every allowed tag
+const GENERIC_ATTRIBUTES: [&str; 8] = ["class", "id", "title", "lang", "dir", "role", "aria-label", "aria-hidden"];
+
+/// Attributes allowed on specific tags
+const TAG_ATTRIBUTES: [(&str, &[&str]); 13] = [
+    ("a", &["href"]),
+    ("img", &["src", "alt", "width", "height", "loading"]),
+    ("video", &["src", "controls", "width", "height", "poster", "muted", "loop", "playsinline"]),
+    ("audio", &["src", "controls", "loop"]),
+    ("source", &["src", "type"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("ol", &["start", "reversed"]),
+    ("blockquote", &["cite"]),
+    ("q", &["cite"]),
+    ("details", &["open"]),
+    ("button", &["type"]),
+    // The youtube shortcode's click-to-load placeholder
+    ("span", &["data-youtube-id", "data-title"]),
+];
+
+/// Schemes allowed in `href` and `src`, relative URLs are always allowed
+const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Tags whose content is dropped along with them
+const DROPPED_CONTENT_TAGS: [&str; 2] = ["script", "style"];
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut sanitizer = ammonia::Builder::empty();
+    sanitizer
+        .tags(HashSet::from(ALLOWED_TAGS))
+        .generic_attributes(HashSet::from(GENERIC_ATTRIBUTES))
+        .tag_attributes(
+            TAG_ATTRIBUTES
+                .iter()
+                .map(|(tag, attributes)| (*tag, attributes.iter().copied().collect()))
+                .collect::<HashMap<_, HashSet<_>>>(),
+        )
+        .url_schemes(HashSet::from(URL_SCHEMES))
+        .clean_content_tags(HashSet::from(DROPPED_CONTENT_TAGS))
+        .link_rel(None);
+    sanitizer
+});
+
+// An opening or closing tag: name, then its attributes
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<\s*/?\s*([a-zA-Z][\w-]*)([^>]*)>").expect("TAG_REGEX: hardcoded pattern is invalid")
+});
+
+// One attribute in a tag: name, then the value if there is one
+static ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([^\s=/"']+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#)
+        .expect("ATTRIBUTE_REGEX: hardcoded pattern is invalid")
+});
+
+/// Applies the `[raw_html] policy` to export blocks, HTML snippets and link targets
+/// and remembers what it had to take out, for the startup report
+#[derive(Debug, Default)]
+pub struct RawHtml {
+    policy: RawHtmlPolicy,
+    // Tags, attributes and URLs outside the allowlist, each listed once
+    removed: Vec<String>,
+}
+
+impl RawHtml {
+    pub fn new(policy: RawHtmlPolicy) -> Self {
+        Self {
+            policy,
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Raw HTML from `#+begin_export html` or `@@html:...@@`, cleaned unless the policy allows anything
+    /// Under `reject` the cleaned HTML is still returned; the report stops the site from starting
+    pub fn filter(&mut self, html: &str) -> String {
+        if self.policy == RawHtmlPolicy::Allow {
+            return html.to_string();
+        }
+        for item in disallowed(html) {
+            self.record(item);
+        }
+        SANITIZER.clean(html).to_string()
+    }
+
+    /// Whether a link target may be written as an `href`, `javascript:` and similar are not
+    pub fn allows_link(&mut self, path: &str) -> bool {
+        if self.policy == RawHtmlPolicy::Allow {
+            return true;
+        }
+        match unsafe_scheme(path) {
+            Some(scheme) => {
+                self.record(format!("{}: link", scheme));
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn record(&mut self, item: String) {
+        if !self.removed.contains(&item) {
+            self.removed.push(item);
+        }
+    }
+}
+
+/// Describe every tag, attribute and URL in `html` that the sanitizer will drop
+fn disallowed(html: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for tag in TAG_REGEX.captures_iter(html) {
+        let name = tag[1].to_lowercase();
+        if !ALLOWED_TAGS.contains(&name.as_str()) {
+            found.push(format!("<{}>", name));
+            continue;
+        }
+
+        let tag_attributes = TAG_ATTRIBUTES
+            .iter()
+            .find(|(tag, _)| *tag == name)
+            .map(|(_, attributes)| *attributes)
+            .unwrap_or_default();
+        for attribute in ATTRIBUTE_REGEX.captures_iter(&tag[2]) {
+            let attribute_name = attribute[1].to_lowercase();
+            if !GENERIC_ATTRIBUTES.contains(&attribute_name.as_str()) && !tag_attributes.contains(&attribute_name.as_str()) {
+                found.push(format!("{} attribute on <{}>", attribute_name, name));
+                continue;
+            }
+            let value = attribute.get(2).or(attribute.get(3)).or(attribute.get(4));
+            if let (true, Some(value)) = (matches!(attribute_name.as_str(), "href" | "src"), value) {
+                let value = html_escape::decode_html_entities(value.as_str());
+                if let Some(scheme) = url_scheme(&value).filter(|scheme| !URL_SCHEMES.contains(&scheme.as_str())) {
+                    found.push(format!("{}: URL in <{}>", scheme, name));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The lowercased scheme of a URL, ignoring the whitespace and control characters browsers skip
+fn url_scheme(url: &str) -> Option<String> {
+    let normalized: String = url.chars().filter(|c| !c.is_whitespace() && !c.is_control()).collect();
+    let (scheme, _) = normalized.split_once(':')?;
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    is_scheme.then(|| scheme.to_lowercase())
+}
+
+/// Schemes that run code or embed documents when an org link is followed
+fn unsafe_scheme(path: &str) -> Option<String> {
+    url_scheme(path).filter(|scheme| matches!(scheme.as_str(), "javascript" | "vbscript" | "data"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sanitize_reports_and_strips() {
+        let mut raw_html = RawHtml::new(RawHtmlPolicy::Sanitize);
+        let html = raw_html.filter(
+            "<p onclick=\"steal()\">Hi<script>alert(1)</script> <a href=\"java\tscript:x()\">x</a> <iframe src=\"https://evil\"></iframe></p>",
+        );
+        assert_eq!(html, "<p>Hi <a>x</a> </p>");
+        assert_eq!(
+            raw_html.removed(),
+            ["onclick attribute on <p>", "<script>", "javascript: URL in <a>", "<iframe>"]
+        );
+    }
+
+    #[test]
+    fn test_allowlisted_html_is_kept() {
+        let mut raw_html = RawHtml::new(RawHtmlPolicy::Reject);
+        let html = "<figure class=\"wide\"><img src=\"/static/a.png\" alt=\"A\"><figcaption>A <kbd>C-x</kbd></figcaption></figure>";
+        assert_eq!(raw_html.filter(html), html);
+        assert!(raw_html.removed().is_empty());
+    }
+
+    #[test]
+    fn test_allow_policy_passes_everything() {
+        let mut raw_html = RawHtml::new(RawHtmlPolicy::Allow);
+        assert_eq!(raw_html.filter("<script>x()</script>"), "<script>x()</script>");
+        assert!(raw_html.allows_link("javascript:x()"));
+        assert!(raw_html.removed().is_empty());
+    }
+
+    #[test]
+    fn test_unsafe_links() {
+        let mut raw_html = RawHtml::new(RawHtmlPolicy::Sanitize);
+        assert!(raw_html.allows_link("https://example.com"));
+        assert!(raw_html.allows_link("file:notes.org"));
+        assert!(!raw_html.allows_link(" JavaScript:alert(1)"));
+        assert!(!raw_html.allows_link("data:text/html,<script>"));
+        assert_eq!(raw_html.removed(), ["javascript: link", "data: link"]);
+    }
+}
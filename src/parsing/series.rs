@@ -0,0 +1,101 @@
+use crate::models::{Post, Series, SeriesPart};
+use std::collections::HashMap;
+
+/// Group posts by `#+SERIES:`, ordered by `#+SERIES_PART:` and then by date
+pub fn collect_series(posts: &HashMap<String, Post>) -> HashMap<String, Series> {
+    let mut members: HashMap<&str, Vec<&Post>> = HashMap::new();
+    // Unlisted posts are left out so the other parts do not advertise them
+    for post in posts.values().filter(|post| post.is_listed()) {
+        if let Some(info) = &post.series {
+            members.entry(info.slug.as_str()).or_default().push(post);
+        }
+    }
+
+    members
+        .into_iter()
+        .map(|(slug, mut posts)| {
+            posts.sort_by_key(|post| {
+                let part = post.series.as_ref().and_then(|info| info.part).unwrap_or(usize::MAX);
+                (part, post.date, post.slug.clone())
+            });
+            warn_duplicate_parts(&posts);
+
+            // Posts may spell the name differently, the first part's spelling wins
+            let name = posts
+                .first()
+                .and_then(|post| post.series.as_ref())
+                .map(|info| info.name.clone())
+                .unwrap_or_default();
+            let parts = posts
+                .iter()
+                .map(|post| SeriesPart {
+                    slug: post.slug.clone(),
+                    title: post.title.clone(),
+                    date: post.date,
+                    description: post.description.clone(),
+                })
+                .collect();
+            (slug.to_string(), Series { name, slug: slug.to_string(), parts })
+        })
+        .collect()
+}
+
+fn warn_duplicate_parts(posts: &[&Post]) {
+    for pair in posts.windows(2) {
+        let parts = pair.iter().map(|post| post.series.as_ref().and_then(|info| info.part)).collect::<Vec<_>>();
+        if let [Some(first), Some(second)] = parts[..] {
+            if first == second {
+                eprintln!(
+                    "Warning: {}.org and {}.org are both #+SERIES_PART: {}, ordered by date",
+                    pair[0].slug, pair[1].slug, first
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SeriesInfo;
+    use crate::utils::slugify;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn post(slug: &str, series: Option<(&str, Option<usize>)>, day: u32) -> (String, Post) {
+        let post = Post {
+            slug: slug.to_string(),
+            title: slug.to_uppercase(),
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            series: series.map(|(name, part)| SeriesInfo {
+                name: name.to_string(),
+                slug: slugify(name),
+                part,
+            }),
+            ..Default::default()
+        };
+        (slug.to_string(), post)
+    }
+
+    #[test]
+    fn test_parts_ordered_by_part_then_date() {
+        let posts = HashMap::from([
+            post("intro", Some(("Nix Setup", Some(1))), 20),
+            post("flakes", Some(("Nix Setup", Some(2))), 5),
+            post("extra", Some(("nix setup", None)), 1),
+            post("direnv", Some(("Nix Setup", Some(3))), 10),
+            post("other", None, 2),
+        ]);
+        let series = collect_series(&posts);
+
+        assert_eq!(series.len(), 1);
+        let nix = &series["nix-setup"];
+        assert_eq!(
+            nix.parts.iter().map(|part| part.slug.as_str()).collect::<Vec<_>>(),
+            vec!["intro", "flakes", "direnv", "extra"]
+        );
+        assert_eq!(nix.name, "Nix Setup");
+        assert_eq!(nix.position("direnv"), Some(3));
+        assert_eq!(nix.position("other"), None);
+    }
+}
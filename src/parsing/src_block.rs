@@ -0,0 +1,118 @@
+use std::ops::RangeInclusive;
+
+/// Line numbering requested by a source block switch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineNumbers {
+    /// `-n [start]` - restart numbering, first line gets `start` (default 1)
+    New(usize),
+    /// `+n [offset]` - continue from the previous numbered block plus `offset`
+    Continued(usize),
+}
+
+/// Switches and header arguments that follow the language of a `#+begin_src` line
+/// e.g. `#+begin_src rust -n 10 :hl_lines 3-5,7`
+#[derive(Debug, Default, PartialEq)]
+pub struct SrcBlockArgs {
+    pub line_numbers: Option<LineNumbers>,
+    /// 1-based line ranges within the block to emphasize
+    pub hl_lines: Vec<RangeInclusive<usize>>,
+}
+
+impl SrcBlockArgs {
+    pub fn parse(arguments: &str) -> Self {
+        let mut args = Self::default();
+        let mut tokens = arguments.split_whitespace().peekable();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "-n" | "+n" => {
+                    let value = tokens.peek().and_then(|t| t.parse::<usize>().ok());
+                    if value.is_some() {
+                        tokens.next();
+                    }
+                    args.line_numbers = Some(if token == "-n" {
+                        LineNumbers::New(value.unwrap_or(1))
+                    } else {
+                        LineNumbers::Continued(value.unwrap_or(0))
+                    });
+                }
+                ":hl_lines" => {
+                    while let Some(value) = tokens.next_if(|t| !t.starts_with([':', '-', '+'])) {
+                        args.hl_lines.extend(parse_line_ranges(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        args
+    }
+
+    pub fn is_highlighted(&self, index: usize) -> bool {
+        self.hl_lines.iter().any(|range| range.contains(&index))
+    }
+}
+
+/// Parse "3-5,7" into [3..=5, 7..=7], ignoring malformed entries; "5-3" means 3..=5
+fn parse_line_ranges(value: &str) -> Vec<RangeInclusive<usize>> {
+    value
+        .trim_matches('"')
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => {
+                let start = start.trim().parse::<usize>().ok()?;
+                let end = end.trim().parse::<usize>().ok()?;
+                Some(start.min(end)..=start.max(end))
+            }
+            None => range.trim().parse::<usize>().ok().map(|line| line..=line),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_empty_arguments() {
+        assert_eq!(SrcBlockArgs::parse(""), SrcBlockArgs::default());
+    }
+
+    #[test]
+    fn test_parse_line_number_switches() {
+        assert_eq!(SrcBlockArgs::parse(" -n").line_numbers, Some(LineNumbers::New(1)));
+        assert_eq!(SrcBlockArgs::parse(" -n 10").line_numbers, Some(LineNumbers::New(10)));
+        assert_eq!(SrcBlockArgs::parse(" +n").line_numbers, Some(LineNumbers::Continued(0)));
+        assert_eq!(SrcBlockArgs::parse(" +n 5").line_numbers, Some(LineNumbers::Continued(5)));
+    }
+
+    #[test]
+    fn test_parse_hl_lines() {
+        let args = SrcBlockArgs::parse(" -n :hl_lines 3-5,7 :results none");
+        assert_eq!(args.hl_lines, vec![3..=5, 7..=7]);
+        assert!(args.is_highlighted(4) && args.is_highlighted(7));
+        assert!(!args.is_highlighted(6));
+        assert_eq!(args.line_numbers, Some(LineNumbers::New(1)));
+    }
+
+    #[test]
+    fn test_parse_hl_lines_ignores_garbage() {
+        let args = SrcBlockArgs::parse(":hl_lines x-2,4");
+        assert_eq!(args.hl_lines, vec![4..=4]);
+    }
+
+    #[test]
+    fn test_parse_hl_lines_reversed_range() {
+        let args = SrcBlockArgs::parse(":hl_lines 5-3");
+        assert_eq!(args.hl_lines, vec![3..=5]);
+        assert!(args.is_highlighted(3) && args.is_highlighted(5));
+    }
+
+    #[test]
+    fn test_parse_hl_lines_huge_range_is_not_expanded() {
+        let args = SrcBlockArgs::parse(":hl_lines 1-4000000000");
+        assert_eq!(args.hl_lines, vec![1..=4_000_000_000]);
+        assert!(args.is_highlighted(2));
+    }
+}
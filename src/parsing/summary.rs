@@ -0,0 +1,131 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Wraps the contents of `#+begin_summary`
+pub const SUMMARY_START: &str = "<!--summary-->";
+pub const SUMMARY_END: &str = "<!--/summary-->";
+/// Written for `#+MORE`, everything before it is the summary
+pub const MORE: &str = "<!--more-->";
+/// Wraps the first paragraph, the summary when a post has neither of the above
+pub const PARAGRAPH_START: &str = "<!--first-paragraph-->";
+pub const PARAGRAPH_END: &str = "<!--/first-paragraph-->";
+
+const MARKERS: [&str; 5] = [SUMMARY_START, SUMMARY_END, MORE, PARAGRAPH_START, PARAGRAPH_END];
+
+/// Elements without a closing tag
+const VOID_TAGS: [&str; 8] = ["area", "br", "col", "hr", "img", "input", "source", "wbr"];
+
+// An opening or closing tag: slash, name, self-closing slash
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<(/?)([a-zA-Z][\w-]*)(?:[^>]*?)(/?)>").expect("TAG_REGEX: hardcoded pattern is invalid")
+});
+
+// The id of a heading, or of a paragraph standing in for a heading below `H:`
+static HEADING_ID_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(<h[1-6]|<p class="heading-deep")\s+id="[^"]*""#)
+        .expect("HEADING_ID_REGEX: hardcoded pattern is invalid")
+});
+
+static PERMALINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)<a class="heading-permalink"[^>]*>.*?</a>"#)
+        .expect("PERMALINK_REGEX: hardcoded pattern is invalid")
+});
+
+// `#+MORE` on a line of its own, which orgize only reads as a keyword with a colon
+static MORE_LINE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?mi)^([ \t]*)#\+more[ \t]*$").expect("MORE_LINE_REGEX: hardcoded pattern is invalid")
+});
+
+/// Let `#+MORE` be written without the colon keywords usually take
+pub fn normalize_more_marker(text: &str) -> String {
+    MORE_LINE_REGEX.replace_all(text, "${1}#+MORE:").into_owned()
+}
+
+/// Split rendered post HTML into the content without markers and the summary:
+/// the `#+begin_summary` block, else everything before `#+MORE`, else the first paragraph
+/// Summaries lose heading ids and permalinks, which belong to the post page
+pub fn split_summary(html: &str) -> (String, String) {
+    let summary = between(html, SUMMARY_START, SUMMARY_END)
+        .or_else(|| {
+            let (before, _) = html.split_once(MORE)?;
+            Some(close_open_tags(before.strip_prefix("<main>").unwrap_or(before)))
+        })
+        .or_else(|| between(html, PARAGRAPH_START, PARAGRAPH_END))
+        .unwrap_or_default();
+    let summary = PERMALINK_REGEX.replace_all(&summary, "");
+    let summary = HEADING_ID_REGEX.replace_all(&summary, "$1");
+    (strip_markers(html), strip_markers(&summary).trim().to_string())
+}
+
+/// Remove every summary marker, for pages that do not use them
+pub fn strip_markers(html: &str) -> String {
+    MARKERS.iter().fold(html.to_string(), |html, marker| html.replace(marker, ""))
+}
+
+fn between(html: &str, start: &str, end: &str) -> Option<String> {
+    let (_, rest) = html.split_once(start)?;
+    let (inner, _) = rest.split_once(end)?;
+    Some(inner.to_string())
+}
+
+/// Append closing tags for elements a cut left open, innermost first
+fn close_open_tags(html: &str) -> String {
+    let mut open: Vec<String> = Vec::new();
+    for tag in TAG_REGEX.captures_iter(html) {
+        let name = tag[2].to_lowercase();
+        if VOID_TAGS.contains(&name.as_str()) || !tag[3].is_empty() {
+            continue;
+        }
+        if tag[1].is_empty() {
+            open.push(name);
+        } else if let Some(index) = open.iter().rposition(|open_name| *open_name == name) {
+            open.truncate(index);
+        }
+    }
+    let mut closed = html.to_string();
+    for name in open.iter().rev() {
+        closed.push_str(&format!("</{}>", name));
+    }
+    closed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_summary_block_wins() {
+        let html = format!(
+            "<main>{}<p>First.</p>{}<div class=\"summary\">{}<p>Summary.</p>{}</div>{}",
+            PARAGRAPH_START, PARAGRAPH_END, SUMMARY_START, SUMMARY_END, MORE
+        );
+        let (content, summary) = split_summary(&html);
+        assert_eq!(summary, "<p>Summary.</p>");
+        assert_eq!(content, "<main><p>First.</p><div class=\"summary\"><p>Summary.</p></div>");
+    }
+
+    #[test]
+    fn test_more_cut_closes_open_tags() {
+        let html = format!(
+            "<main><h1 id=\"a\">A<a class=\"heading-permalink\" href=\"#a\">¶</a></h1><section>{}<p>One<br>two</p>{}\
+             <p>Rest.</p></section></main>",
+            PARAGRAPH_START, MORE
+        );
+        let (content, summary) = split_summary(&html);
+        assert_eq!(summary, "<h1>A</h1><section><p>One<br>two</p></section>");
+        assert!(content.contains("<h1 id=\"a\">A<a class=\"heading-permalink\""));
+    }
+
+    #[test]
+    fn test_first_paragraph_fallback() {
+        let html = format!("<main><section>{}<p>Intro.</p>{}<p>More.</p></section></main>", PARAGRAPH_START, PARAGRAPH_END);
+        assert_eq!(split_summary(&html).1, "<p>Intro.</p>");
+        assert_eq!(split_summary("<main></main>").1, "");
+    }
+
+    #[test]
+    fn test_normalize_more_marker() {
+        assert_eq!(normalize_more_marker("Intro\n#+more\nRest\n#+MORE:\n"), "Intro\n#+MORE:\nRest\n#+MORE:\n");
+    }
+}
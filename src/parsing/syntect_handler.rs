@@ -1,163 +1,854 @@
-use crate::models::TocItem;
-use orgize::{export::{DefaultHtmlHandler, HtmlHandler}, Element};
+use crate::models::{CalloutStyle, SiteConfig, TocItem};
+use super::anchors::{clean_org_links, HeadingAnchors};
+use super::callouts::{icon_emoji, icon_name, icon_svg};
+use super::citations::Citations;
+use super::charts::{render_chart, ChartSpec, TableData};
+use super::diagrams::DiagramKind;
+use super::options::ExportOptions;
+use super::raw_html::RawHtml;
+use super::src_block::{LineNumbers, SrcBlockArgs};
+use super::summary::{MORE, PARAGRAPH_END, PARAGRAPH_START, SUMMARY_END, SUMMARY_START};
+use super::text::sub_superscripts;
+use super::typography::Typography;
+use orgize::{elements::{Link, SourceBlock, SpecialBlock, Table, Title}, export::{DefaultHtmlHandler, HtmlHandler}, Element};
+use std::collections::HashMap;
 use std::io::Write;
-use syntect::{html::ClassedHTMLGenerator, parsing::SyntaxSet, util::LinesWithEndings};
+use std::sync::LazyLock;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, Theme},
+    html::{line_tokens_to_classed_spans, styled_line_to_highlighted_html, ClassStyle, IncludeBackground},
+    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+// syntect's default syntaxes merged with syntaxes/, serialized by build.rs
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(|| {
+    syntect::dumps::from_uncompressed_data(include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/syntaxes.packdump"
+    )))
+    .expect("SYNTAX_SET: embedded syntax dump is invalid")
+});
 
 pub struct SyntectHandler {
-    syntax_set: SyntaxSet,
+    syntax_set: &'static SyntaxSet,
+    aliases: HashMap<String, String>,
+    callouts: HashMap<String, CalloutStyle>,
+    // When set, code is emitted with inline styles from this theme instead of CSS classes
+    inline_theme: Option<Theme>,
+    // Languages that had no syntax definition and were rendered as plain text
+    fallback_languages: Vec<String>,
+    options: ExportOptions,
     default: DefaultHtmlHandler,
     toc: Vec<TocItem>,
     heading_counter: usize,
+    anchors: HeadingAnchors,
+    // Id of the heading being written, for its permalink
+    current_heading: Option<String>,
+    // Internal link targets (`#id`, `*Heading`) that matched no heading
+    unresolved_links: Vec<String>,
+    citations: Citations,
+    raw_html: RawHtml,
+    // Smart punctuation for text events, when enabled in site.toml
+    typography: Option<Typography>,
+    // One entry per open headline, true when it was opened as a collapsible <details>
+    collapsible: Vec<bool>,
+    // Whether to mark the summary for `split_summary`, and where the first paragraph marking stands
+    summary_markers: bool,
+    first_paragraph: FirstParagraph,
+    // Open blocks and lists around the current element, the first paragraph must be outside them
+    block_depth: usize,
+    // Counters for the numbered heading levels, e.g. [2, 1] while inside section 2.1
+    section_numbers: Vec<usize>,
+    // Affiliated keywords (#+NAME:, #+CAPTION:) waiting for the element they precede
+    pending_name: Option<String>,
+    pending_caption: Option<String>,
+    pending_chart: Option<String>,
+    // Table annotated with #+ATTR_CHART:, buffered until its data is complete
+    chart: Option<ChartCapture>,
+    // Last number emitted by a numbered source block, so `+n` can continue from it
+    last_line_number: usize,
+    // Diagrams get ids `<prefix>-1`, `<prefix>-2`, ... so their SVG ids stay unique on the page
+    diagram_prefix: String,
+    diagram_count: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum FirstParagraph {
+    #[default]
+    Pending,
+    Open,
+    Written,
+}
+
+struct ChartCapture {
+    spec: Result<ChartSpec, String>,
+    caption: Option<String>,
+    table: TableData,
+    html: Vec<u8>,
 }
 
 impl Default for SyntectHandler {
     fn default() -> Self {
         Self {
-            syntax_set: SyntaxSet::load_defaults_newlines(),
+            syntax_set: &SYNTAX_SET,
+            aliases: HashMap::new(),
+            callouts: HashMap::new(),
+            inline_theme: None,
+            fallback_languages: Vec::new(),
+            options: ExportOptions::default(),
             default: DefaultHtmlHandler,
             toc: Vec::new(),
             heading_counter: 0,
+            anchors: HeadingAnchors::default(),
+            current_heading: None,
+            unresolved_links: Vec::new(),
+            citations: Citations::default(),
+            raw_html: RawHtml::default(),
+            typography: None,
+            collapsible: Vec::new(),
+            summary_markers: false,
+            first_paragraph: FirstParagraph::Pending,
+            block_depth: 0,
+            section_numbers: Vec::new(),
+            pending_name: None,
+            pending_caption: None,
+            pending_chart: None,
+            chart: None,
+            last_line_number: 0,
+            diagram_prefix: "diagram".to_string(),
+            diagram_count: 0,
         }
     }
 }
 
 impl SyntectHandler {
+    pub fn new(config: &SiteConfig) -> Self {
+        Self {
+            aliases: config.highlight.aliases.clone(),
+            callouts: config.callouts.clone(),
+            raw_html: RawHtml::new(config.raw_html.policy),
+            typography: config
+                .typography
+                .enabled
+                .then(|| Typography::new(config.typography.hyphenate_min_length)),
+            ..Self::default()
+        }
+    }
+
+    /// Handler for HTML read off-site (feeds, email) where our stylesheets are not loaded
+    /// Code is colored with inline styles from `theme` instead of CSS classes
+    pub fn inline_styled(config: &SiteConfig, theme: Theme) -> Self {
+        Self {
+            inline_theme: Some(theme),
+            ..Self::new(config)
+        }
+    }
+
+    /// Render with a file's `#+OPTIONS:` (see `ExportOptions::for_file`)
+    pub fn with_options(self, options: ExportOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Use heading ids assigned up front (see `HeadingAnchors::for_org`) so links can point forward
+    pub fn with_anchors(self, anchors: HeadingAnchors) -> Self {
+        Self { anchors, ..self }
+    }
+
+    /// Resolve `[cite:@key]` against a bibliography and list the cited works at the end
+    pub fn with_citations(self, citations: Citations) -> Self {
+        Self { citations, ..self }
+    }
+
+    /// Prefix for diagram SVG ids, which must differ between posts shown on one page
+    pub fn with_diagram_prefix(self, diagram_prefix: String) -> Self {
+        Self { diagram_prefix, ..self }
+    }
+
+    /// Mark `#+begin_summary`, `#+MORE` and the first paragraph for `split_summary`
+    pub fn with_summary_markers(self) -> Self {
+        Self {
+            summary_markers: true,
+            ..self
+        }
+    }
+
+    /// Source block languages that fell back to plain text
+    pub fn fallback_languages(&self) -> &[String] {
+        &self.fallback_languages
+    }
+
+    /// Internal links that did not match any heading
+    pub fn unresolved_links(&self) -> &[String] {
+        &self.unresolved_links
+    }
+
+    /// Raw HTML tags, attributes and link schemes removed by the `[raw_html]` policy
+    pub fn removed_raw_html(&self) -> &[String] {
+        self.raw_html.removed()
+    }
+
+    /// Cited keys that are not in the bibliography
+    pub fn unknown_citations(&self) -> &[String] {
+        self.citations.unknown_keys()
+    }
+
     pub fn into_toc(self) -> Vec<TocItem> {
         self.toc
     }
 
-    /// Clean org-mode link syntax from text
-    /// Converts [[URL][link text]] to "link text"
-    /// Converts [[URL]] to "URL"
-    fn clean_org_links(text: &str) -> String {
-        let mut result = String::new();
-        let mut chars = text.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '[' && chars.peek() == Some(&'[') {
-                chars.next(); // consume second '['
-
-                // Extract the URL part
-                let mut url = String::new();
-                while let Some(c) = chars.next() {
-                    if c == ']' && chars.peek() == Some(&']') {
-                        chars.next(); // consume second ']'
-                        // No link text, use URL
-                        result.push_str(&url);
-                        break;
-                    } else if c == ']' && chars.peek() == Some(&'[') {
-                        chars.next(); // consume '['
-                        // Extract link text
-                        let mut link_text = String::new();
-                        while let Some(t) = chars.next() {
-                            if t == ']' && chars.peek() == Some(&']') {
-                                chars.next(); // consume second ']'
-                                result.push_str(&link_text);
-                                break;
-                            } else {
-                                link_text.push(t);
-                            }
-                        }
-                        break;
-                    } else {
-                        url.push(c);
-                    }
+    /// Resolve a source block language through the alias map, falling back to plain text
+    fn find_syntax(&mut self, lang: &str) -> &'static SyntaxReference {
+        let syntax_set = self.syntax_set;
+        let resolved = self.aliases.get(lang).map(String::as_str).unwrap_or(lang);
+
+        match syntax_set.find_syntax_by_token(resolved) {
+            Some(syntax) => syntax,
+            None => {
+                if !lang.is_empty() && !self.fallback_languages.iter().any(|l| l == lang) {
+                    self.fallback_languages.push(lang.to_string());
+                }
+                syntax_set.find_syntax_plain_text()
+            }
+        }
+    }
+
+    /// Highlight code into one HTML string per line
+    /// Scopes still open at the end of a line are closed there and reopened on the
+    /// next one, so every line can be wrapped in its own element
+    fn highlight_lines(&mut self, lang: &str, contents: &str) -> Result<Vec<String>, syntect::Error> {
+        let syntax = self.find_syntax(lang);
+
+        if let Some(theme) = &self.inline_theme {
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let mut lines = Vec::new();
+            for line in LinesWithEndings::from(contents) {
+                let regions = highlighter.highlight_line(line, self.syntax_set)?;
+                let html = styled_line_to_highlighted_html(&regions, IncludeBackground::No)?;
+                lines.push(html.replacen('\n', "", 1));
+            }
+            return Ok(lines);
+        }
+
+        let mut parse_state = ParseState::new(syntax);
+        let mut scope_stack = ScopeStack::new();
+        let mut lines = Vec::new();
+
+        for line in LinesWithEndings::from(contents) {
+            let mut html = String::new();
+            for scope in scope_stack.as_slice() {
+                html.push_str(&format!(
+                    "<span class=\"{}\">",
+                    scope.build_string().replace('.', " ")
+                ));
+            }
+
+            let ops = parse_state.parse_line(line, self.syntax_set)?;
+            let (spans, _) =
+                line_tokens_to_classed_spans(line, &ops, ClassStyle::Spaced, &mut scope_stack)?;
+            // The newline is written between line elements instead
+            html.push_str(&spans.replacen('\n', "", 1));
+
+            for _ in 0..scope_stack.len() {
+                html.push_str("</span>");
+            }
+            lines.push(html);
+        }
+
+        Ok(lines)
+    }
+
+    /// Render a block's contents as (html, extra line class) pairs
+    fn render_lines(&mut self, lang: &str, contents: &str) -> Vec<(String, &'static str)> {
+        let rendered = if is_console_language(lang) {
+            self.console_lines(contents)
+        } else {
+            self.highlight_lines(lang, contents).map(|lines| {
+                lines
+                    .into_iter()
+                    .zip(contents.lines())
+                    .map(|(html, raw)| (html, if lang == "diff" { diff_line_kind(raw) } else { "" }))
+                    .collect()
+            })
+        };
+
+        rendered.unwrap_or_else(|e| {
+            eprintln!("Syntax highlighting error: {}", e);
+            // Fall back to plain text rendering
+            contents
+                .lines()
+                .map(|line| (html_escape::encode_text(line).into_owned(), ""))
+                .collect()
+        })
+    }
+
+    /// Split a console session into prompt, command and output lines
+    /// Commands (and their `\` continuations) are highlighted together as one shell script
+    fn console_lines(&mut self, contents: &str) -> Result<Vec<(String, &'static str)>, syntect::Error> {
+        let mut parsed = Vec::new();
+        let mut continues = false;
+        for line in contents.lines() {
+            let command = if continues {
+                Some(("", line))
+            } else {
+                split_prompt(line)
+            };
+            continues = command.is_some_and(|(_, cmd)| cmd.ends_with('\\'));
+            parsed.push((line, command));
+        }
+
+        let script = parsed
+            .iter()
+            .filter_map(|(_, command)| command.map(|(_, cmd)| format!("{}\n", cmd)))
+            .collect::<String>();
+        let mut highlighted = self.highlight_lines("bash", &script)?.into_iter();
+
+        Ok(parsed
+            .into_iter()
+            .map(|(line, command)| match command {
+                Some((prompt, _)) => (
+                    format!(
+                        "<span class=\"prompt\">{}</span><span class=\"command\">{}</span>",
+                        html_escape::encode_text(prompt),
+                        highlighted.next().unwrap_or_default()
+                    ),
+                    "command-line",
+                ),
+                None => (html_escape::encode_text(line).into_owned(), "output"),
+            })
+            .collect())
+    }
+
+    fn write_source_block<W: Write>(
+        &mut self,
+        mut w: W,
+        block: &SourceBlock,
+    ) -> Result<(), std::io::Error> {
+        let lang = block.language.as_ref();
+        if let Some(kind) = DiagramKind::from_language(lang) {
+            return self.write_diagram(w, kind, block);
+        }
+
+        let args = SrcBlockArgs::parse(&block.arguments);
+
+        let lines = self.render_lines(lang, &block.contents);
+
+        let first_number = match args.line_numbers {
+            Some(LineNumbers::New(start)) => Some(start),
+            Some(LineNumbers::Continued(offset)) => Some(self.last_line_number + offset + 1),
+            None => None,
+        };
+
+        write!(w, "<div class=\"code-block\">")?;
+
+        let name = self.pending_name.take();
+        let caption = self.pending_caption.take();
+        if name.is_some() || caption.is_some() {
+            write!(w, "<div class=\"code-header\">")?;
+            if let Some(name) = name {
+                write!(w, "<span class=\"code-filename\">{}</span>", html_escape::encode_text(&name))?;
+            }
+            if let Some(caption) = caption {
+                write!(w, "<span class=\"code-caption\">{}</span>", html_escape::encode_text(&caption))?;
+            }
+            write!(w, "</div>")?;
+        }
+
+        let mut pre_classes = String::from("code");
+        if is_console_language(lang) {
+            pre_classes.push_str(" console");
+        }
+        if first_number.is_some() {
+            pre_classes.push_str(" line-numbers");
+        }
+        match &self.inline_theme {
+            Some(theme) => write!(
+                w,
+                "<pre class=\"{}\" style=\"{}\"><code>",
+                pre_classes,
+                inline_pre_style(theme)
+            )?,
+            None => write!(w, "<pre class=\"{}\"><code>", pre_classes)?,
+        }
+
+        for (index, (html, kind)) in lines.iter().enumerate() {
+            let highlighted = args.is_highlighted(index + 1);
+            let mut classes = String::from("line");
+            if highlighted {
+                classes.push_str(" hl");
+            }
+            if !kind.is_empty() {
+                classes.push(' ');
+                classes.push_str(kind);
+            }
+
+            write!(w, "<span class=\"{}\"", classes)?;
+            if let Some(first) = first_number {
+                write!(w, " data-line=\"{}\"", first + index)?;
+            }
+
+            // Off-site readers have no stylesheet, so line decorations are spelled out inline
+            if let Some(theme) = &self.inline_theme {
+                let style = inline_line_style(theme, highlighted, kind);
+                if !style.is_empty() {
+                    write!(w, " style=\"{}\"", style)?;
+                }
+                write!(w, ">")?;
+                if let Some(first) = first_number {
+                    write!(w, "<span style=\"opacity:0.5;user-select:none\">{:>3} </span>", first + index)?;
                 }
             } else {
-                result.push(ch);
+                write!(w, ">")?;
+            }
+
+            writeln!(w, "{}</span>", html)?;
+        }
+
+        write!(w, "</code></pre></div>")?;
+
+        if let Some(first) = first_number {
+            self.last_line_number = first + lines.len().saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    /// Render a diagram block as inline SVG with its source in a collapsible `<details>`
+    /// If the diagram fails to render, show the error and open the source instead
+    fn write_diagram<W: Write>(
+        &mut self,
+        mut w: W,
+        kind: DiagramKind,
+        block: &SourceBlock,
+    ) -> Result<(), std::io::Error> {
+        self.pending_name = None;
+        let caption = self.pending_caption.take();
+
+        write!(w, "<figure class=\"diagram diagram-{}\">", kind.name())?;
+
+        self.diagram_count += 1;
+        let rendered = kind.render(&block.contents, &format!("{}-{}", self.diagram_prefix, self.diagram_count));
+        match &rendered {
+            Ok(svg) => write!(w, "<div class=\"diagram-svg\">{}</div>", svg)?,
+            Err(error) => write!(
+                w,
+                "<div class=\"diagram-error\" role=\"alert\"><strong>Diagram error:</strong> {}</div>",
+                html_escape::encode_text(error)
+            )?,
+        }
+
+        // svgbob sources are ASCII art, there is nothing to highlight
+        let source_lang = match kind {
+            DiagramKind::Dot => "dot",
+            DiagramKind::Svgbob => "txt",
+        };
+        write!(
+            w,
+            "<details class=\"diagram-source\"{}><summary>Diagram source</summary><pre class=\"code\"><code>",
+            if rendered.is_err() { " open" } else { "" }
+        )?;
+        for (html, _) in self.render_lines(source_lang, &block.contents) {
+            writeln!(w, "<span class=\"line\">{}</span>", html)?;
+        }
+        write!(w, "</code></pre></details>")?;
+
+        if let Some(caption) = caption {
+            write!(w, "<figcaption>{}</figcaption>", html_escape::encode_text(&caption))?;
+        }
+        write!(w, "</figure>")
+    }
+
+    /// Open a special block: a callout when its name is configured, otherwise a plain div
+    fn start_special_block<W: Write>(&mut self, mut w: W, block: &SpecialBlock) -> Result<(), std::io::Error> {
+        let name = block.name.to_lowercase();
+        let Some(style) = self.callouts.get(&name) else {
+            write!(w, "<div class=\"{}\">", html_escape::encode_double_quoted_attribute(&name))?;
+            if name == "summary" && self.summary_markers {
+                write!(w, "{}", SUMMARY_START)?;
+            }
+            return Ok(());
+        };
+
+        // `#+begin_warning Title here` overrides the configured title
+        let title = block
+            .parameters
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or(&style.title);
+        let icon = icon_name(&name, style);
+
+        if self.inline_theme.is_some() {
+            // Feed readers drop our styles, so fall back to a quote with an emoji marker
+            write!(
+                w,
+                "<blockquote class=\"callout callout-{}\"><p><strong>{} {}</strong></p>",
+                icon,
+                icon_emoji(icon),
+                html_escape::encode_text(title)
+            )
+        } else {
+            write!(
+                w,
+                "<div class=\"callout callout-{}\" role=\"note\"><p class=\"callout-title\">{}<span>{}</span></p><div class=\"callout-body\">",
+                icon,
+                icon_svg(icon),
+                html_escape::encode_text(title)
+            )
+        }
+    }
+
+    fn end_special_block<W: Write>(&mut self, mut w: W, block: &SpecialBlock) -> Result<(), std::io::Error> {
+        let name = block.name.to_lowercase();
+        if !self.callouts.contains_key(&name) {
+            if name == "summary" && self.summary_markers {
+                write!(w, "{}", SUMMARY_END)?;
             }
+            write!(w, "</div>")
+        } else if self.inline_theme.is_some() {
+            write!(w, "</blockquote>")
+        } else {
+            write!(w, "</div></div>")
         }
+    }
 
-        result
+    /// Write a link, unless its scheme runs code, in which case only its text is kept
+    fn write_link<W: Write>(&mut self, mut w: W, link: &Link) -> Result<(), std::io::Error> {
+        if link.path.starts_with(['#', '*']) {
+            return self.write_internal_link(w, link);
+        }
+        if self.raw_html.allows_link(&link.path) {
+            return self.default.start(w, &Element::Link(link.clone()));
+        }
+        write!(
+            w,
+            "<span class=\"broken-link\">{}</span>",
+            html_escape::encode_text(link.desc.as_deref().unwrap_or(&link.path))
+        )
     }
 
-    /// Convert text to URL-friendly slug
-    /// "What's Nix?" -> "whats-nix"
-    /// "Setting Up Direnv" -> "setting-up-direnv"
-    fn slugify(text: &str) -> String {
-        text.to_lowercase()
-            .chars()
-            .map(|c| {
-                if c.is_alphanumeric() {
-                    c
-                } else if c.is_whitespace() {
-                    '-'
-                } else {
-                    ' ' // Will be filtered out
+    /// Link to a heading in this document, labelled with the heading's text when the link has none
+    fn write_internal_link<W: Write>(&mut self, mut w: W, link: &Link) -> Result<(), std::io::Error> {
+        match self.anchors.resolve(&link.path) {
+            Some((id, text)) => write!(
+                w,
+                "<a href=\"#{}\">{}</a>",
+                html_escape::encode_double_quoted_attribute(id),
+                html_escape::encode_text(link.desc.as_deref().unwrap_or(text))
+            ),
+            None => {
+                if !self.unresolved_links.iter().any(|path| path == link.path.as_ref()) {
+                    self.unresolved_links.push(link.path.to_string());
                 }
-            })
-            .collect::<String>()
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join("")
-            .split('-')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join("-")
+                let text = link
+                    .desc
+                    .as_deref()
+                    .unwrap_or_else(|| link.path.trim_start_matches(['#', '*']));
+                write!(w, "<span class=\"broken-link\">{}</span>", html_escape::encode_text(text))
+            }
+        }
+    }
+
+    fn end_collapsible_summary<W: Write>(&mut self, mut w: W) -> Result<(), std::io::Error> {
+        if self.collapsible.last() == Some(&true) {
+            write!(w, "</summary>")?;
+        }
+        Ok(())
+    }
+
+    /// Write the references section, listed in the TOC like a top-level heading
+    fn write_references<W: Write>(&mut self, mut w: W) -> Result<(), std::io::Error> {
+        let Some(list) = self.citations.references_html() else {
+            return Ok(());
+        };
+        let id = self.anchors.unique_id("references");
+        if self.options.in_toc(1) {
+            self.toc.push(TocItem {
+                id: id.clone(),
+                text: "References".to_string(),
+                level: 1,
+                number: None,
+            });
+        }
+
+        let id = html_escape::encode_double_quoted_attribute(&id);
+        write!(w, "<section class=\"references-section\"><h1 id=\"{}\">References", id)?;
+        if self.inline_theme.is_none() {
+            write!(
+                w,
+                "<a class=\"heading-permalink\" href=\"#{}\" aria-label=\"Link to this section\">¶</a>",
+                id
+            )?;
+        }
+        write!(w, "</h1>{}</section>", list)
+    }
+
+    /// Write a buffered chart table: the SVG chart (or an error) followed by the table itself
+    fn write_chart<W: Write>(&mut self, mut w: W, chart: ChartCapture) -> Result<(), std::io::Error> {
+        write!(w, "<figure class=\"chart\">")?;
+        let rendered = chart
+            .spec
+            .and_then(|spec| render_chart(&spec, &chart.table, chart.caption.as_deref()));
+        match rendered {
+            Ok(svg) => write!(w, "{}", svg)?,
+            Err(error) => write!(
+                w,
+                "<div class=\"chart-error\" role=\"alert\"><strong>Chart error:</strong> {}</div>",
+                html_escape::encode_text(&error)
+            )?,
+        }
+        if let Some(caption) = &chart.caption {
+            write!(w, "<figcaption>{}</figcaption>", html_escape::encode_text(caption))?;
+        }
+        write!(w, "</figure>")?;
+        w.write_all(&chart.html)
+    }
+}
+
+fn css_color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn inline_pre_style(theme: &Theme) -> String {
+    let mut style = String::from("padding:1em;overflow-x:auto;border-radius:5px;");
+    if let Some(background) = theme.settings.background {
+        style.push_str(&format!("background-color:{};", css_color(background)));
+    }
+    if let Some(foreground) = theme.settings.foreground {
+        style.push_str(&format!("color:{};", css_color(foreground)));
+    }
+    style
+}
+
+fn inline_line_style(theme: &Theme, highlighted: bool, kind: &str) -> String {
+    let background = if highlighted {
+        Some(
+            theme
+                .settings
+                .line_highlight
+                .map(css_color)
+                .unwrap_or_else(|| "rgba(128,128,128,0.2)".to_string()),
+        )
+    } else {
+        match kind {
+            "diff-add" => Some("rgba(46,160,67,0.15)".to_string()),
+            "diff-del" => Some("rgba(248,81,73,0.15)".to_string()),
+            _ => None,
+        }
+    };
+
+    match (background, kind) {
+        (Some(background), _) => format!("display:inline-block;min-width:100%;background-color:{}", background),
+        (None, "output") => "opacity:0.7".to_string(),
+        (None, _) => String::new(),
+    }
+}
+
+/// Headings tagged `:collapsed:` or with `:HTML_CONTAINER: details` fold into a `<details>` element
+fn is_collapsible(title: &Title) -> bool {
+    title.tags.iter().any(|tag| tag == "collapsed")
+        || title
+            .properties
+            .iter()
+            .any(|(key, value)| key.eq_ignore_ascii_case("HTML_CONTAINER") && value.trim() == "details")
+}
+
+fn is_console_language(lang: &str) -> bool {
+    matches!(lang, "console" | "shell-session")
+}
+
+fn diff_line_kind(line: &str) -> &'static str {
+    if line.starts_with('+') && !line.starts_with("+++") {
+        "diff-add"
+    } else if line.starts_with('-') && !line.starts_with("---") {
+        "diff-del"
+    } else {
+        ""
     }
 }
 
+/// Split a console line into its prompt (with leading indentation) and command
+/// Returns None for output lines
+fn split_prompt(line: &str) -> Option<(&str, &str)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let prompt_len = ['$', '#', '>'].iter().find_map(|prompt| {
+        let after = rest.strip_prefix(*prompt)?;
+        if after.is_empty() {
+            Some(1)
+        } else if after.starts_with(' ') {
+            Some(2)
+        } else {
+            None
+        }
+    })?;
+    Some(line.split_at(indent + prompt_len))
+}
+
+/// Greater elements that can hold paragraphs, other than headlines and sections
+fn is_block_container(element: &Element) -> bool {
+    matches!(
+        element,
+        Element::SpecialBlock(_)
+            | Element::QuoteBlock(_)
+            | Element::CenterBlock(_)
+            | Element::Drawer(_)
+            | Element::DynBlock(_)
+            | Element::FnDef(_)
+            | Element::List(_)
+    )
+}
+
 impl HtmlHandler<std::io::Error> for SyntectHandler {
     fn start<W: Write>(
         &mut self,
         mut w: W,
         element: &Element,
     ) -> Result<(), std::io::Error> {
+        // Everything inside a chart table is buffered so the chart can be written first
+        if let Some(mut chart) = self.chart.take() {
+            chart.table.record(element);
+            let result = self.start(&mut chart.html, element);
+            self.chart = Some(chart);
+            return result;
+        }
+
+        if let Some(typography) = &mut self.typography {
+            typography.observe(element);
+        }
+        if is_block_container(element) {
+            self.block_depth += 1;
+        }
+
+        // Affiliated keywords only apply to the element directly following them
+        if !matches!(element, Element::Keyword(_) | Element::SourceBlock(_) | Element::Table(_)) {
+            self.pending_name = None;
+            self.pending_caption = None;
+            self.pending_chart = None;
+        }
+
         match element {
-            Element::SourceBlock(block) => {
-                let lang = block.language.as_ref();
-                let syntax = self
-                    .syntax_set
-                    .find_syntax_by_token(lang)
-                    .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
-
-                let mut generator = ClassedHTMLGenerator::new_with_class_style(
-                    syntax,
-                    &self.syntax_set,
-                    syntect::html::ClassStyle::Spaced,
-                );
-
-                for line in LinesWithEndings::from(&block.contents) {
-                    if let Err(e) = generator.parse_html_for_line_which_includes_newline(line) {
-                        eprintln!("Syntax highlighting error: {}", e);
-                        // Fall back to plain text rendering
-                        let escaped = html_escape::encode_text(&block.contents);
-                        write!(w, "<pre class=\"code\"><code>{}</code></pre>", escaped)?;
-                        return Ok(());
-                    }
+            Element::SourceBlock(block) => self.write_source_block(w, block),
+            Element::SpecialBlock(block) => self.start_special_block(w, block),
+            Element::Keyword(keyword) => {
+                match keyword.key.to_uppercase().as_str() {
+                    "NAME" => self.pending_name = Some(keyword.value.trim().to_string()),
+                    "CAPTION" => self.pending_caption = Some(keyword.value.trim().to_string()),
+                    "ATTR_CHART" => self.pending_chart = Some(keyword.value.trim().to_string()),
+                    "MORE" if self.summary_markers => write!(w, "{}", MORE)?,
+                    _ => {}
                 }
-
-                let html = generator.finalize();
-                write!(w, "<pre class=\"code\"><code>{}</code></pre>", html)?;
+                self.default.start(w, element)
+            }
+            Element::Table(Table::Org { .. }) if self.pending_chart.is_some() => {
+                let mut chart = ChartCapture {
+                    spec: ChartSpec::parse(self.pending_chart.take().as_deref().unwrap_or_default()),
+                    caption: self.pending_caption.take(),
+                    table: TableData::default(),
+                    html: Vec::new(),
+                };
+                self.pending_name = None;
+                self.default.start(&mut chart.html, element)?;
+                self.chart = Some(chart);
+                Ok(())
+            }
+            Element::Table(_) => {
+                self.pending_name = None;
+                self.pending_caption = None;
+                self.pending_chart = None;
+                self.default.start(w, element)
+            }
+            Element::Headline { .. } => {
+                self.collapsible.push(false);
                 Ok(())
             }
             Element::Title(title) => {
                 // Clean org-mode link syntax from title text
-                let clean_text = Self::clean_org_links(title.raw.trim());
-
-                // Generate slug from cleaned text
-                let slug = Self::slugify(&clean_text);
+                let mut clean_text = clean_org_links(title.raw.trim());
+                if self.typography.is_some() {
+                    clean_text = Typography::new(0).apply(&clean_text);
+                }
 
-                // Handle potential duplicates by appending counter if needed
-                let id = if self.toc.iter().any(|item| item.id == slug) {
-                    format!("{}-{}", slug, self.heading_counter)
-                } else {
-                    slug
-                };
+                // Feeds get the section unfolded, readers there often ignore <details>
+                if is_collapsible(title) && self.inline_theme.is_none() {
+                    if let Some(open) = self.collapsible.last_mut() {
+                        *open = true;
+                        write!(w, "<details class=\"collapsible\"><summary>")?;
+                    }
+                }
 
+                let id = self.anchors.id_for(self.heading_counter, title);
                 self.heading_counter += 1;
 
-                // Add to TOC
-                self.toc.push(TocItem {
-                    id: id.clone(),
-                    text: clean_text,
-                    level: title.level as usize,
+                // Headlines below `H:` are exported as plain paragraphs, outside the TOC and numbering
+                if !self.options.is_heading(title.level) {
+                    write!(w, "<p class=\"heading-deep\" id=\"{}\">", html_escape::encode_double_quoted_attribute(&id))?;
+                    return Ok(());
+                }
+
+                let number = self.options.is_numbered(title.level).then(|| {
+                    self.section_numbers.resize(title.level, 0);
+                    self.section_numbers[title.level - 1] += 1;
+                    self.section_numbers
+                        .iter()
+                        .map(usize::to_string)
+                        .collect::<Vec<_>>()
+                        .join(".")
                 });
 
+                // Add to TOC
+                if self.options.in_toc(title.level) {
+                    self.toc.push(TocItem {
+                        id: id.clone(),
+                        text: clean_text,
+                        level: title.level,
+                        number: number.clone(),
+                    });
+                }
+
                 // Write the heading with ID
-                write!(w, "<h{} id=\"{}\">", title.level, id)?;
+                write!(w, "<h{} id=\"{}\">", title.level, html_escape::encode_double_quoted_attribute(&id))?;
+                self.current_heading = Some(id);
+                if let Some(number) = number {
+                    write!(w, "<span class=\"section-number\">{}</span> ", number)?;
+                }
                 Ok(())
             }
+            // A paragraph in a callout, list or collapsed section does not stand for the whole post
+            Element::Paragraph { .. }
+                if self.summary_markers
+                    && self.first_paragraph == FirstParagraph::Pending
+                    && self.block_depth == 0
+                    && !self.collapsible.contains(&true) =>
+            {
+                self.first_paragraph = FirstParagraph::Open;
+                write!(w, "{}", PARAGRAPH_START)?;
+                self.default.start(w, element)
+            }
+            Element::Link(link) => self.write_link(w, link),
+            Element::ExportBlock(block) if block.data.eq_ignore_ascii_case("html") => {
+                write!(w, "{}", self.raw_html.filter(&block.contents))
+            }
+            Element::Snippet(snippet) if snippet.name.eq_ignore_ascii_case("html") => {
+                write!(w, "{}", self.raw_html.filter(&snippet.value))
+            }
+            // orgize writes the language into the class attribute unescaped
+            Element::InlineSrc(inline_src) => write!(
+                w,
+                "<code class=\"src src-{}\">{}</code>",
+                html_escape::encode_double_quoted_attribute(&inline_src.lang),
+                html_escape::encode_text(&inline_src.body)
+            ),
+            Element::Text { value } => {
+                let mode = self.options.sub_superscripts;
+                let typography = &mut self.typography;
+                let html = self.citations.render(value, |plain| match typography {
+                    Some(typography) => sub_superscripts(&typography.apply(plain), mode),
+                    None => sub_superscripts(plain, mode),
+                });
+                write!(w, "{}", html)
+            }
             _ => self.default.start(w, element),
         }
     }
@@ -167,13 +858,477 @@ impl HtmlHandler<std::io::Error> for SyntectHandler {
         mut w: W,
         element: &Element,
     ) -> Result<(), std::io::Error> {
+        if let Some(mut chart) = self.chart.take() {
+            self.end(&mut chart.html, element)?;
+            if matches!(element, Element::Table(_)) {
+                return self.write_chart(w, chart);
+            }
+            self.chart = Some(chart);
+            return Ok(());
+        }
+        if is_block_container(element) {
+            self.block_depth = self.block_depth.saturating_sub(1);
+        }
+
         match element {
             Element::SourceBlock(_) => Ok(()),
+            Element::SpecialBlock(block) => self.end_special_block(w, block),
+            Element::Paragraph { .. } if self.first_paragraph == FirstParagraph::Open => {
+                self.first_paragraph = FirstParagraph::Written;
+                self.default.end(&mut w, element)?;
+                write!(w, "{}", PARAGRAPH_END)
+            }
+            Element::Title(title) if !self.options.is_heading(title.level) => {
+                write!(w, "</p>")?;
+                self.end_collapsible_summary(w)
+            }
             Element::Title(title) => {
+                // Off-site copies have no use for a link back into the same page
+                if let Some(id) = self.current_heading.take().filter(|_| self.inline_theme.is_none()) {
+                    write!(
+                        w,
+                        "<a class=\"heading-permalink\" href=\"#{}\" aria-label=\"Link to this section\">¶</a>",
+                        html_escape::encode_double_quoted_attribute(&id)
+                    )?;
+                }
                 write!(w, "</h{}>", title.level)?;
+                self.end_collapsible_summary(w)
+            }
+            Element::Headline { .. } => {
+                if self.collapsible.pop() == Some(true) {
+                    write!(w, "</details>")?;
+                }
                 Ok(())
             }
+            Element::Document { .. } => {
+                self.write_references(&mut w)?;
+                self.default.end(w, element)
+            }
             _ => self.default.end(w, element),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CitationStyle, RawHtmlPolicy};
+    use crate::parsing::bibtex::Bibliography;
+    use orgize::Org;
+    use std::sync::Arc;
+
+    fn render(text: &str) -> String {
+        let mut handler = SyntectHandler::default();
+        let mut html = Vec::new();
+        Org::parse(text)
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        String::from_utf8(html).expect("Should be valid UTF-8")
+    }
+
+    #[test]
+    fn test_source_block_filename_header() {
+        let html = render("#+NAME: src/main.rs\n#+begin_src rust\nfn main() {}\n#+end_src\n");
+        assert!(html.contains("<span class=\"code-filename\">src/main.rs</span>"));
+        // The header sits outside the <code> element the copy button reads
+        assert!(html.find("code-header").unwrap() < html.find("<code>").unwrap());
+    }
+
+    #[test]
+    fn test_name_does_not_leak_to_later_blocks() {
+        let html = render(
+            "#+NAME: first.rs\n#+begin_src rust\nfn a() {}\n#+end_src\n\nText\n\n#+begin_src rust\nfn b() {}\n#+end_src\n",
+        );
+        assert_eq!(html.matches("code-header").count(), 1);
+    }
+
+    #[test]
+    fn test_source_block_line_numbers_continue() {
+        let html = render(
+            "#+begin_src sh -n\necho one\necho two\n#+end_src\n\n#+begin_src sh +n\necho three\n#+end_src\n",
+        );
+        assert!(html.contains("class=\"code line-numbers\""));
+        assert!(html.contains("data-line=\"1\""));
+        assert!(html.contains("data-line=\"2\""));
+        assert!(html.contains("data-line=\"3\""));
+    }
+
+    #[test]
+    fn test_source_block_hl_lines_and_diff() {
+        let html = render("#+begin_src diff :hl_lines 2\n-old\n+new\n context\n#+end_src\n");
+        assert!(html.contains("<span class=\"line diff-del\">"));
+        assert!(html.contains("<span class=\"line hl diff-add\">"));
+        assert!(html.contains("<span class=\"line\">"));
+    }
+
+    #[test]
+    fn test_bundled_syntaxes_are_available() {
+        for lang in ["nix", "toml", "dockerfile", "zig", "just"] {
+            assert_ne!(
+                SYNTAX_SET.find_syntax_by_token(lang).map(|s| s.name.as_str()),
+                None,
+                "missing syntax for {}",
+                lang
+            );
+        }
+    }
+
+    #[test]
+    fn test_language_aliases_and_fallbacks() {
+        let mut config = SiteConfig::load().expect("Should load config");
+        config.highlight.aliases.insert("elisp".to_string(), "lisp".to_string());
+        let mut handler = SyntectHandler::new(&config);
+        let mut html = Vec::new();
+        Org::parse("#+begin_src elisp\n(message \"hi\")\n#+end_src\n#+begin_src klingon\nqapla'\n#+end_src\n")
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+
+        assert!(String::from_utf8(html).unwrap().contains("source lisp"));
+        assert_eq!(handler.fallback_languages(), ["klingon"]);
+    }
+
+    #[test]
+    fn test_inline_styled_mode() {
+        let theme = syntect::highlighting::ThemeSet::load_defaults()
+            .themes
+            .remove("InspiredGitHub")
+            .unwrap();
+        let config = SiteConfig::load().expect("Should load config");
+        let mut handler = SyntectHandler::inline_styled(&config, theme);
+        let mut html = Vec::new();
+        Org::parse("#+begin_src rust :hl_lines 1\nfn main() {}\n#+end_src\n")
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("<pre class=\"code\" style=\""));
+        assert!(html.contains("<span style=\"color:#"));
+        assert!(html.contains("background-color:"));
+        assert!(!html.contains("class=\"source rust\""));
+    }
+
+    #[test]
+    fn test_split_prompt() {
+        assert_eq!(split_prompt("$ ls -la"), Some(("$ ", "ls -la")));
+        assert_eq!(split_prompt("  # apt update"), Some(("  # ", "apt update")));
+        assert_eq!(split_prompt("$"), Some(("$", "")));
+        assert_eq!(split_prompt("total 42"), None);
+        assert_eq!(split_prompt("$HOME is set"), None);
+    }
+
+    #[test]
+    fn test_console_block_separates_commands_and_output() {
+        let html = render("#+begin_src console\n$ echo hi \\\n  there\nhi there\n#+end_src\n");
+        assert!(html.contains("<pre class=\"code console\">"));
+        assert_eq!(html.matches("class=\"line command-line\"").count(), 2);
+        assert!(html.contains("<span class=\"line output\">hi there</span>"));
+        assert!(html.contains("<span class=\"prompt\">$ </span><span class=\"command\">"));
+    }
+
+    #[test]
+    fn test_highlighted_lines_are_balanced() {
+        let html = render("#+begin_src rust\n/* multi\nline */\nfn main() {}\n#+end_src\n");
+        assert_eq!(html.matches("<span").count(), html.matches("</span>").count());
+    }
+
+    #[test]
+    fn test_diagram_block_renders_svg_with_source() {
+        let html = render("#+CAPTION: Flow\n#+begin_src dot\ndigraph { a -> b; }\n#+end_src\n");
+        assert!(html.contains("<figure class=\"diagram diagram-dot\">"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("<details class=\"diagram-source\"><summary>"));
+        assert!(html.contains("<figcaption>Flow</figcaption>"));
+    }
+
+    #[test]
+    fn test_diagrams_in_one_document_have_unique_ids() {
+        let block = "#+begin_src dot\ndigraph { a -> b; b -> c; }\n#+end_src\n";
+        let html = render(&format!("{}\n{}", block, block));
+
+        let ids: Vec<&str> = html.split(" id=\"").skip(1).filter_map(|rest| rest.split('"').next()).collect();
+        assert!(ids.contains(&"diagram-1-endarrow") && ids.contains(&"diagram-2-endarrow"));
+        let unique: std::collections::HashSet<&str> = ids.iter().copied().collect();
+        assert_eq!(unique.len(), ids.len(), "Duplicate ids in {:?}", ids);
+        assert!(html.contains("url(#diagram-2-endarrow)"));
+        assert!(html.contains(".diagram-1-a14 {") && html.contains(".diagram-2-a14 {"));
+    }
+
+    #[test]
+    fn test_diagram_error_opens_source() {
+        let html = render("#+begin_src dot\ndigraph { a -> ; }\n#+end_src\n");
+        assert!(html.contains("<div class=\"diagram-error\" role=\"alert\">"));
+        assert!(html.contains("<details class=\"diagram-source\" open>"));
+        assert!(!html.contains("<svg"));
+    }
+
+    #[test]
+    fn test_chart_table_renders_svg_above_table() {
+        let html = render(
+            "#+CAPTION: Throughput\n#+ATTR_CHART: :type bar :x 1 :y 2\n| size | ops |\n|------+-----|\n| 1    | 10  |\n| 2    | 25  |\n",
+        );
+        let svg = html.find("<svg").expect("Should render a chart");
+        assert!(svg < html.find("<table>").expect("Should keep the table"));
+        assert!(html.contains("<figcaption>Throughput</figcaption>"));
+        assert!(html.contains("<td>25</td>"));
+    }
+
+    #[test]
+    fn test_chart_error_keeps_table() {
+        let html = render("#+ATTR_CHART: :type pie\n| a | 1 |\n");
+        assert!(html.contains("<div class=\"chart-error\" role=\"alert\">"));
+        assert!(html.contains("<td>a</td>"));
+    }
+
+    #[test]
+    fn test_plain_tables_are_not_charts() {
+        let html = render("| a | 1 |\n");
+        assert!(!html.contains("chart"));
+    }
+
+    fn render_with_config(text: &str, feed: bool) -> String {
+        let config = SiteConfig::load().expect("Should load config");
+        let mut handler = if feed {
+            SyntectHandler::inline_styled(&config, crate::parsing::load_theme("ef-maris-light").unwrap())
+        } else {
+            SyntectHandler::new(&config)
+        };
+        let mut html = Vec::new();
+        Org::parse(text)
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        String::from_utf8(html).expect("Should be valid UTF-8")
+    }
+
+    #[test]
+    fn test_callout_blocks() {
+        let html = render_with_config("#+begin_warning\nCareful.\n#+end_warning\n", false);
+        assert!(html.contains("<div class=\"callout callout-warning\" role=\"note\"><p class=\"callout-title\"><svg"));
+        assert!(html.contains("<span>Warning</span>"));
+        assert!(html.contains("<div class=\"callout-body\"><p>Careful.</p>"));
+        assert_eq!(html.matches("<div").count(), html.matches("</div>").count());
+    }
+
+    #[test]
+    fn test_callout_custom_title_and_feed_fallback() {
+        let html = render_with_config("#+begin_danger Do not run as root\nNever.\n#+end_danger\n", true);
+        assert!(html.contains("<blockquote class=\"callout callout-danger\"><p><strong>🛑 Do not run as root</strong></p>"));
+        assert!(html.contains("<p>Never.</p></blockquote>"));
+        assert!(!html.contains("<svg"));
+    }
+
+    #[test]
+    fn test_unknown_special_blocks_stay_divs() {
+        let html = render_with_config("#+begin_aside\nText\n#+end_aside\n", false);
+        assert!(html.contains("<div class=\"aside\"><p>Text</p></div>"));
+    }
+
+    fn render_with_options(text: &str, site_options: &str) -> (String, Vec<TocItem>) {
+        let org = Org::parse(text);
+        let mut handler = SyntectHandler::default().with_options(ExportOptions::for_file(site_options, &org));
+        let mut html = Vec::new();
+        org.write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        (String::from_utf8(html).expect("Should be valid UTF-8"), handler.into_toc())
+    }
+
+    #[test]
+    fn test_toc_depth_and_numbering() {
+        let (html, toc) = render_with_options(
+            "#+OPTIONS: toc:1 num:t\n* One\n** One A\n* Two\n** Two A\n** Two B\n",
+            "",
+        );
+        let toc: Vec<_> = toc.iter().map(|item| (item.text.as_str(), item.number.as_deref())).collect();
+        assert_eq!(toc, vec![("One", Some("1")), ("Two", Some("2"))]);
+        assert!(html.contains("<h2 id=\"two-b\"><span class=\"section-number\">2.2</span> Two B<a class=\"heading-permalink\""));
+    }
+
+    #[test]
+    fn test_toc_nil_and_headline_levels() {
+        let (html, toc) = render_with_options("* Top\n** Deep\nBody\n", "toc:nil H:1");
+        assert!(toc.is_empty());
+        assert!(html.contains("<h1 id=\"top\">Top<a class=\"heading-permalink\""));
+        assert!(html.contains("<p class=\"heading-deep\" id=\"deep\">Deep</p>"));
+    }
+
+    #[test]
+    fn test_sub_superscript_option() {
+        let (html, _) = render_with_options("#+OPTIONS: ^:t\nx^2 and H_{2}O\n", "");
+        assert!(html.contains("x<sup>2</sup> and H<sub>2</sub>O"));
+        let (html, _) = render_with_options("x^2 and H_{2}O\n", "^:nil");
+        assert!(html.contains("x^2 and H_{2}O"));
+        let (html, _) = render_with_options("x^2 and H_{2}O\n", "");
+        assert!(html.contains("x^2 and H_{2}O"), "Literal unless site.toml or the file opts in");
+    }
+
+    #[test]
+    fn test_heading_permalinks() {
+        let html = render("* Setup\n* Setup\n");
+        assert!(html.contains(
+            "<h1 id=\"setup\">Setup<a class=\"heading-permalink\" href=\"#setup\" aria-label=\"Link to this section\">¶</a></h1>"
+        ));
+        assert!(html.contains("<h1 id=\"setup-2\">"));
+    }
+
+    #[test]
+    fn test_internal_links_resolve_forward() {
+        let text = "See [[#dive]] and [[*Deep Dive][the dive]], not [[*Nowhere]].\n* Deep Dive\n:PROPERTIES:\n:CUSTOM_ID: dive\n:END:\n";
+        let org = Org::parse(text);
+        let mut handler = SyntectHandler::default().with_anchors(HeadingAnchors::for_org(&org));
+        let mut html = Vec::new();
+        org.write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("<a href=\"#dive\">Deep Dive</a>"));
+        assert!(html.contains("<a href=\"#dive\">the dive</a>"));
+        assert!(html.contains("<h1 id=\"dive\">"));
+        assert!(html.contains("<span class=\"broken-link\">Nowhere</span>"));
+        assert_eq!(handler.unresolved_links(), ["*Nowhere"]);
+    }
+
+    #[test]
+    fn test_typography_skips_code() {
+        let mut config = SiteConfig::load().expect("Should load config");
+        config.typography.enabled = true;
+        let mut handler = SyntectHandler::new(&config);
+        let mut html = Vec::new();
+        Org::parse("* It's \"done\"\nSay \"hi\" -- see ~\"raw\" -- code~...\n#+begin_src sh\necho \"x\" -- y\n#+end_src\n")
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("It’s “done”"));
+        assert!(html.contains("Say “hi” – see <code>&quot;raw&quot; -- code</code>…"));
+        // Straight quotes survive in highlighted source blocks
+        assert!(html.contains("&quot;</span>x<span"));
+        assert!(!html.contains("“x"));
+        assert_eq!(handler.into_toc()[0].text, "It’s “done”");
+    }
+
+    #[test]
+    fn test_raw_html_policy() {
+        let mut config = SiteConfig::load().expect("Should load config");
+        config.raw_html.policy = RawHtmlPolicy::Reject;
+        let text = crate::parsing::macros::expand_macros(
+            "{{{kbd(C-c)}}} {{{youtube(dQw4w9WgXcQ)}}} @@html:<b onmouseover=\"x()\">b</b>@@ [[javascript:x()][click]]\n\
+             src_x\"onclick=\"y(){1}\n#+begin_export html\n<script>z()</script><em>ok</em>\n#+end_export\n",
+        )
+        .unwrap();
+        let mut handler = SyntectHandler::new(&config);
+        let mut html = Vec::new();
+        Org::parse(&text)
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let html = String::from_utf8(html).unwrap();
+
+        // Shortcode output is inside the allowlist, so even `reject` lets it through
+        assert!(html.contains("<kbd class=\"keys\"><kbd>C-c</kbd></kbd>"));
+        assert!(html.contains("data-youtube-id=\"dQw4w9WgXcQ\""));
+        assert!(html.contains("<b>b</b>"));
+        assert!(html.contains("<span class=\"broken-link\">click</span>"));
+        assert!(html.contains("<code class=\"src src-x&quot;onclick=&quot;y()\">1</code>"));
+        assert!(html.contains("<em>ok</em>") && !html.contains("<script>"));
+        assert_eq!(
+            handler.removed_raw_html(),
+            ["onmouseover attribute on <b>", "javascript: link", "<script>"]
+        );
+    }
+
+    #[test]
+    fn test_collapsible_sections() {
+        let html = render(
+            "* Logs :collapsed:\nHuge dump\n** Nested\nMore\n* Solution\n:PROPERTIES:\n:HTML_CONTAINER: details\n:END:\nSpoiler\n* Open\n",
+        );
+        assert!(html.contains(
+            "<details class=\"collapsible\"><summary><h1 id=\"logs\">Logs<a class=\"heading-permalink\" href=\"#logs\" aria-label=\"Link to this section\">¶</a></h1></summary>"
+        ));
+        // The nested heading stays inside the folded section, which closes before the next one
+        let nested = html.find("id=\"nested\"").unwrap();
+        let first_close = html.find("</details>").unwrap();
+        assert!(nested < first_close && first_close < html.find("id=\"solution\"").unwrap());
+        assert!(html.contains("<details class=\"collapsible\"><summary><h1 id=\"solution\">"));
+        assert_eq!(html.matches("<details").count(), 2);
+        assert_eq!(html.matches("</details>").count(), 2);
+    }
+
+    #[test]
+    fn test_nested_collapsible_sections_close_at_sibling_and_parent() {
+        let html = render(
+            "* Outer :collapsed:\nA\n** Inner :collapsed:\nB\n** Sibling\nC\n*** Deep :collapsed:\nD\n* Next\n",
+        );
+        let position = |needle: &str| html.find(needle).unwrap();
+        let closes: Vec<usize> = html.match_indices("</details>").map(|(index, _)| index).collect();
+        assert_eq!(closes.len(), 3);
+        // Inner closes at its sibling, Deep and Outer both close at the next top-level heading
+        assert!(position("id=\"inner\"") < closes[0] && closes[0] < position("id=\"sibling\""));
+        assert!(position("id=\"deep\"") < closes[1] && closes[2] < position("id=\"next\""));
+        assert_eq!(html.matches("<details class=\"collapsible\">").count(), 3);
+    }
+
+    #[test]
+    fn test_collapsible_section_at_end_of_document() {
+        let html = render("* Intro\nHello\n* Appendix :collapsed:\nThe end\n");
+        assert_eq!(html.matches("<details class=\"collapsible\">").count(), 1);
+        assert_eq!(html.matches("</details>").count(), 1);
+        assert!(html.find("The end").unwrap() < html.find("</details>").unwrap());
+    }
+
+    #[test]
+    fn test_citations_and_references_section() {
+        let bibliography = Bibliography::parse("@book{sicp, author = {Abelson, Harold}, title = {SICP}, year = 1985}").unwrap();
+        let mut handler = SyntectHandler::default()
+            .with_citations(Citations::new(Arc::new(bibliography), CitationStyle::Numeric));
+        let mut html = Vec::new();
+        Org::parse("* References
+As in [cite:@sicp], ~[cite:@code]~ and [cite:@nope].
+")
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("As in <span class=\"citation\">[<a href=\"#ref-sicp\">1</a>]</span>"));
+        assert!(html.contains("<code>[cite:@code]</code>"));
+        // The section id steps around the heading that already took "references"
+        assert!(html.contains("<section class=\"references-section\"><h1 id=\"references-2\">References"));
+        assert!(html.contains("<li id=\"ref-sicp\">Harold Abelson. <cite>SICP</cite>. 1985.</li>"));
+        assert!(html.ends_with("</section></main>"));
+        assert_eq!(handler.unknown_citations(), ["nope"]);
+        assert_eq!(handler.into_toc().last().map(|item| item.id.clone()), Some("references-2".to_string()));
+    }
+
+    fn render_summary(text: &str) -> (String, String) {
+        let mut handler = SyntectHandler::default().with_summary_markers();
+        let mut html = Vec::new();
+        Org::parse(&crate::parsing::summary::normalize_more_marker(text))
+            .write_html_custom(&mut html, &mut handler)
+            .expect("Should render org content");
+        crate::parsing::summary::split_summary(&String::from_utf8(html).expect("Should be valid UTF-8"))
+    }
+
+    #[test]
+    fn test_summary_sources() {
+        let (content, summary) = render_summary("Intro.\n\n#+begin_summary\nThe *gist*.\n#+end_summary\n\nRest.\n");
+        assert_eq!(summary, "<p>The <b>gist</b>.</p>");
+        assert!(content.contains("<div class=\"summary\"><p>The <b>gist</b>.</p></div>"));
+        assert!(!content.contains("<!--"));
+
+        let (content, summary) = render_summary("* Setup\nIntro.\n#+MORE\nRest.\n");
+        // The post page keeps the heading's id and permalink, the index must not repeat them
+        assert!(summary.starts_with("<h1>Setup</h1>"), "{}", summary);
+        assert!(summary.ends_with("<p>Intro.</p></section>"));
+        assert!(content.contains("<h1 id=\"setup\">Setup<a class=\"heading-permalink\""));
+        assert!(content.contains("<p>Intro.</p><p>Rest.</p>"));
+
+        let (_, summary) = render_summary("#+TITLE: T\n\nFirst paragraph.\n\nSecond.\n");
+        assert_eq!(summary, "<p>First paragraph.</p>");
+    }
+
+    #[test]
+    fn test_first_paragraph_is_top_level() {
+        let (_, summary) = render_summary(
+            "#+begin_warning\nIn a callout.\n#+end_warning\n\n- In a list\n\n#+begin_quote\nQuoted.\n#+end_quote\n\n\
+             * Details :collapsed:\nHidden.\n* Body\nThe real intro.\n\nMore.\n",
+        );
+        assert_eq!(summary, "<p>The real intro.</p>");
+    }
+}
@@ -0,0 +1,97 @@
+use super::options::SubSuperscripts;
+
+/// Escape plain text, turning `a^b`/`a_b` markup into `<sup>`/`<sub>` as allowed by `mode`
+pub fn sub_superscripts(text: &str, mode: SubSuperscripts) -> String {
+    if mode == SubSuperscripts::Off {
+        return html_escape::encode_text(text).into_owned();
+    }
+
+    let mut html = String::with_capacity(text.len());
+    let mut plain_start = 0;
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        let follows_text = previous.is_some_and(|p| !p.is_whitespace());
+        previous = Some(c);
+        if !(c == '^' || c == '_') || !follows_text {
+            continue;
+        }
+
+        let rest = &text[index + 1..];
+        let Some((script, len)) = script_at(rest, mode) else {
+            continue;
+        };
+
+        html.push_str(&html_escape::encode_text(&text[plain_start..index]));
+        let tag = if c == '^' { "sup" } else { "sub" };
+        html.push_str(&format!("<{}>{}</{}>", tag, html_escape::encode_text(script), tag));
+
+        plain_start = index + 1 + len;
+        while chars.next_if(|(i, _)| *i < plain_start).is_some() {}
+        previous = text[..plain_start].chars().next_back();
+    }
+
+    html.push_str(&html_escape::encode_text(&text[plain_start..]));
+    html
+}
+
+/// The script following `^`/`_` and the number of bytes it spans, including braces
+fn script_at(rest: &str, mode: SubSuperscripts) -> Option<(&str, usize)> {
+    if let Some(inner) = rest.strip_prefix('{') {
+        let end = inner.find('}')?;
+        return (end > 0).then(|| (&inner[..end], end + 2));
+    }
+    if mode != SubSuperscripts::On {
+        return None;
+    }
+    if rest.starts_with('*') {
+        return Some(("*", 1));
+    }
+
+    // An optional sign, then word characters ending in an alphanumeric: `x^-1`, `a_i2`, `e^{...}`
+    let sign = usize::from(rest.starts_with(['+', '-']));
+    let body_len: usize = rest[sign..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, ',' | '.' | '\\'))
+        .map(char::len_utf8)
+        .sum();
+    let body = rest[sign..sign + body_len].trim_end_matches(|c: char| !c.is_alphanumeric());
+    if body.is_empty() {
+        return None;
+    }
+    let len = sign + body.len();
+    Some((&rest[..len], len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_off_only_escapes() {
+        assert_eq!(sub_superscripts("x^2 < y_{1}", SubSuperscripts::Off), "x^2 &lt; y_{1}");
+    }
+
+    #[test]
+    fn test_braced_mode() {
+        assert_eq!(
+            sub_superscripts("H_{2}O and snake_case, x^{n+1}", SubSuperscripts::Braced),
+            "H<sub>2</sub>O and snake_case, x<sup>n+1</sup>"
+        );
+    }
+
+    #[test]
+    fn test_on_mode() {
+        assert_eq!(
+            sub_superscripts("E = mc^2, a_i, 10^-3.", SubSuperscripts::On),
+            "E = mc<sup>2</sup>, a<sub>i</sub>, 10<sup>-3</sup>."
+        );
+    }
+
+    #[test]
+    fn test_scripts_need_preceding_text() {
+        assert_eq!(sub_superscripts("_private ^{x}", SubSuperscripts::On), "_private ^{x}");
+    }
+}
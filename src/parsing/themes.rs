@@ -0,0 +1,100 @@
+use crate::models::HighlightConfig;
+use anyhow::{Context, Result};
+use rust_embed::RustEmbed;
+use std::io::Cursor;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{css_for_theme_with_class_style, ClassStyle};
+
+#[derive(RustEmbed)]
+#[folder = "themes/"]
+struct Themes;
+
+/// Look up a theme by name: a `themes/<name>.tmTheme` file first, then syntect's bundled themes
+pub fn load_theme(name: &str) -> Result<Theme> {
+    if let Some(file) = Themes::get(&format!("{}.tmTheme", name)) {
+        return ThemeSet::load_from_reader(&mut Cursor::new(file.data.as_ref()))
+            .with_context(|| format!("Failed to parse theme file: themes/{}.tmTheme", name));
+    }
+
+    let mut defaults = ThemeSet::load_defaults();
+    defaults.themes.remove(name).with_context(|| {
+        let mut available: Vec<String> = Themes::iter()
+            .filter(|file| file.ends_with(".tmTheme"))
+            .map(|file| file.trim_end_matches(".tmTheme").to_string())
+            .chain(defaults.themes.keys().cloned())
+            .collect();
+        available.sort();
+        format!(
+            "Unknown highlight theme '{}' (available: {})",
+            name,
+            available.join(", ")
+        )
+    })
+}
+
+/// Build the code highlighting stylesheet from the configured light and dark themes
+pub fn highlight_css(config: &HighlightConfig) -> Result<String> {
+    let mut css = String::new();
+
+    for (data_theme, theme_name) in [("light", &config.light_theme), ("dark", &config.dark_theme)] {
+        let theme = load_theme(theme_name)?;
+        let theme_css = css_for_theme_with_class_style(&theme, ClassStyle::Spaced)
+            .with_context(|| format!("Failed to generate CSS for theme '{}'", theme_name))?;
+        css.push_str(&scope_css(&theme_css, data_theme));
+        css.push('\n');
+    }
+
+    Ok(css)
+}
+
+/// Prefix every selector with `[data-theme="..."]` so both themes can live in one file
+/// syntect's `.code` rule targets our `<pre class="code">` and takes over its background
+fn scope_css(css: &str, data_theme: &str) -> String {
+    css.lines()
+        .map(|line| match line.strip_suffix(" {") {
+            Some(selectors) => {
+                let scoped = selectors
+                    .split(", ")
+                    .map(|selector| {
+                        let selector = if selector == ".code" { "pre.code" } else { selector };
+                        format!("[data-theme=\"{}\"] {}", data_theme, selector)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} {{", scoped)
+            }
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_load_bundled_and_embedded_themes() {
+        assert!(load_theme("ef-maris-dark").is_ok());
+        assert!(load_theme("InspiredGitHub").is_ok());
+        assert!(load_theme("does-not-exist").is_err());
+    }
+
+    #[test]
+    fn test_scope_css_prefixes_every_selector() {
+        let css = "/* theme */\n.code {\n color: #fff;\n}\n.comment, .string .quoted {\n color: #000;\n}";
+        assert_eq!(
+            scope_css(css, "dark"),
+            "/* theme */\n[data-theme=\"dark\"] pre.code {\n color: #fff;\n}\n\
+             [data-theme=\"dark\"] .comment, [data-theme=\"dark\"] .string .quoted {\n color: #000;\n}"
+        );
+    }
+
+    #[test]
+    fn test_highlight_css_contains_both_themes() {
+        let css = highlight_css(&HighlightConfig::default()).expect("Should build CSS");
+        assert!(css.contains("[data-theme=\"light\"] pre.code"));
+        assert!(css.contains("[data-theme=\"dark\"] pre.code"));
+    }
+}
@@ -0,0 +1,222 @@
+use hyphenation::{Hyphenator, Language, Load, Standard};
+use orgize::Element;
+use std::sync::LazyLock;
+
+// US English Knuth-Liang patterns, bundled by the hyphenation crate
+static HYPHENATOR: LazyLock<Standard> = LazyLock::new(|| {
+    Standard::from_embedded(Language::EnglishUS).expect("HYPHENATOR: embedded dictionary is invalid")
+});
+
+/// Stands in for an inline object (code, link, snippet) when deciding which way a quote curls
+const INLINE_OBJECT: char = '\u{fffc}';
+
+const NBSP: char = '\u{a0}';
+const SOFT_HYPHEN: char = '\u{ad}';
+
+/// Units kept on the same line as the number before them, as in `10 MB`
+const UNITS: [&str; 36] = [
+    "%", "‰", "°C", "°F", "B", "kB", "KB", "MB", "GB", "TB", "KiB", "MiB", "GiB", "TiB", "bit", "bits", "ns", "µs",
+    "ms", "s", "min", "h", "Hz", "kHz", "MHz", "GHz", "mm", "cm", "m", "km", "g", "kg", "px", "em", "rem", "W",
+];
+
+/// Smart punctuation for the text of a document, fed one orgize text event at a time
+/// Code, verbatim, source and example blocks and export snippets (such as `kbd`) never
+/// arrive as text events, so they keep their straight quotes and dashes
+#[derive(Debug, Default)]
+pub struct Typography {
+    /// Words at least this many letters long get soft hyphens, 0 turns hyphenation off
+    hyphenate_min_length: usize,
+    /// Last character written in the current block, `None` at the start of one
+    previous: Option<char>,
+}
+
+impl Typography {
+    pub fn new(hyphenate_min_length: usize) -> Self {
+        Self {
+            hyphenate_min_length,
+            previous: None,
+        }
+    }
+
+    /// Track where text events sit: block elements start afresh, inline objects count as a word
+    pub fn observe(&mut self, element: &Element) {
+        match element {
+            Element::Text { .. } | Element::Bold | Element::Italic | Element::Strike | Element::Underline => {}
+            Element::Code { .. }
+            | Element::Verbatim { .. }
+            | Element::Link(_)
+            | Element::Snippet(_)
+            | Element::InlineSrc(_)
+            | Element::InlineCall(_)
+            | Element::Macros(_)
+            | Element::Timestamp(_)
+            | Element::FnRef(_)
+            | Element::Cookie(_)
+            | Element::Target(_) => self.previous = Some(INLINE_OBJECT),
+            _ => self.previous = None,
+        }
+    }
+
+    /// Curly quotes, dashes and ellipses, non-breaking spaces and hyphenation hints for plain text
+    pub fn apply(&mut self, text: &str) -> String {
+        let starts_word = self.previous.is_none_or(opens_quote);
+        let punctuated = self.punctuate(text);
+        let spaced = non_breaking_spaces(&punctuated, starts_word);
+        if self.hyphenate_min_length == 0 {
+            return spaced;
+        }
+        hyphenate(&spaced, self.hyphenate_min_length)
+    }
+
+    /// `"` and `'` curl by what precedes them; `---`, `--` and `...` become `—`, `–` and `…`
+    fn punctuate(&mut self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let opening = self.previous.is_none_or(opens_quote);
+            let replacement = match c {
+                '"' if opening => '“',
+                '"' => '”',
+                // An apostrophe also starts elisions like '90s
+                '\'' if opening && !chars.peek().is_some_and(char::is_ascii_digit) => '‘',
+                '\'' => '’',
+                '-' if chars.next_if_eq(&'-').is_some() => {
+                    if chars.next_if_eq(&'-').is_some() {
+                        '—'
+                    } else {
+                        '–'
+                    }
+                }
+                '.' if text_continues_with(&mut chars, "..") => '…',
+                c => c,
+            };
+            result.push(replacement);
+            self.previous = Some(replacement);
+        }
+        result
+    }
+}
+
+/// Consume `expected` if the iterator continues with it, leaving it untouched otherwise
+fn text_continues_with(chars: &mut std::iter::Peekable<std::str::Chars>, expected: &str) -> bool {
+    let rest: String = chars.clone().take(expected.chars().count()).collect();
+    if rest != expected {
+        return false;
+    }
+    for _ in expected.chars() {
+        chars.next();
+    }
+    true
+}
+
+fn opens_quote(previous: char) -> bool {
+    previous.is_whitespace() || matches!(previous, '(' | '[' | '{' | '“' | '‘' | '–' | '—' | '/')
+}
+
+/// Keep units with their number (`10 MB`) and one- or two-letter words with the word after them
+/// `starts_word` says whether the text's first word is a whole word or the tail of an earlier one
+fn non_breaking_spaces(text: &str, starts_word: bool) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut result = String::with_capacity(text.len());
+    for (index, word) in words.iter().enumerate() {
+        result.push_str(word);
+        let Some(next) = words.get(index + 1) else {
+            break;
+        };
+
+        let bare = word.trim_start_matches(['(', '[', '“', '‘']);
+        let short_word = (index > 0 || starts_word)
+            && (1..=2).contains(&bare.chars().count())
+            && bare.chars().all(char::is_alphabetic);
+        let unit = word.ends_with(|c: char| c.is_ascii_digit())
+            && UNITS.contains(&next.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '”', '’']));
+        result.push(if short_word || unit { NBSP } else { ' ' });
+    }
+    result
+}
+
+/// Insert soft hyphens into long words so the browser may break them at a line end
+fn hyphenate(text: &str, min_length: usize) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word_start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphabetic(), word_start) {
+            (true, None) => word_start = Some(index),
+            (false, Some(start)) => {
+                result.push_str(&hyphenate_word(&text[start..index], min_length));
+                word_start = None;
+                if index < text.len() {
+                    result.push(c);
+                }
+            }
+            (false, None) if index < text.len() => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+fn hyphenate_word(word: &str, min_length: usize) -> String {
+    // Acronyms and short words are left alone
+    if word.chars().count() < min_length || word.chars().all(char::is_uppercase) {
+        return word.to_string();
+    }
+    let breaks = HYPHENATOR.hyphenate(word).breaks;
+    let mut hyphenated = String::with_capacity(word.len() + breaks.len() * 2);
+    let mut last = 0;
+    for index in breaks {
+        hyphenated.push_str(&word[last..index]);
+        hyphenated.push(SOFT_HYPHEN);
+        last = index;
+    }
+    hyphenated.push_str(&word[last..]);
+    hyphenated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn apply(text: &str) -> String {
+        Typography::new(0).apply(text)
+    }
+
+    #[test]
+    fn test_quotes_dashes_and_ellipses() {
+        assert_eq!(
+            apply("\"Don't,\" she said -- 'twice' in the '90s --- wait..."),
+            "“Don’t,” she said – ‘twice’ in\u{a0}the ’90s — wait…"
+        );
+    }
+
+    #[test]
+    fn test_quotes_across_inline_markup() {
+        // "*bold*" arrives as a quote, a bold span's text and another quote
+        let mut typography = Typography::default();
+        typography.observe(&Element::Paragraph { post_blank: 0 });
+        assert_eq!(typography.apply("Say \""), "Say “");
+        typography.observe(&Element::Bold);
+        assert_eq!(typography.apply("bold"), "bold");
+        assert_eq!(typography.apply("\" or \""), "” or\u{a0}“");
+        typography.observe(&Element::Code { value: "code".into() });
+        assert_eq!(typography.apply("\"."), "”.");
+    }
+
+    #[test]
+    fn test_non_breaking_spaces() {
+        assert_eq!(
+            apply("The build took 250 ms, used 3 GB, so I ran it on a box."),
+            "The build took 250\u{a0}ms, used 3\u{a0}GB, so\u{a0}I\u{a0}ran it\u{a0}on\u{a0}a\u{a0}box."
+        );
+        assert_eq!(apply("250 msec"), "250 msec");
+    }
+
+    #[test]
+    fn test_hyphenation_hints() {
+        assert_eq!(
+            Typography::new(10).apply("hyphenation of extraordinary words, not HTTPSCONNECTION"),
+            "hy\u{ad}phen\u{ad}a\u{ad}tion of\u{a0}ex\u{ad}tra\u{ad}or\u{ad}di\u{ad}nary words, not HTTPSCONNECTION"
+        );
+    }
+}
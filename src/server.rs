@@ -1,7 +1,11 @@
-use crate::handlers::{about, index, not_found, post, robots, rss, search, serve_static, sitemap};
-use crate::models::{AppState, SiteConfig};
-use crate::parsing::{parse_about, parse_posts};
-use anyhow::{Context, Result};
+use crate::handlers::{
+    about, author, index, not_found, post, robots, rss, search, series, serve_highlight_css, serve_static, unlock,
+    sitemap, stats,
+};
+use crate::models::{AppState, FingerprintedAsset, SiteConfig};
+use crate::utils::UnlockKey;
+use crate::parsing::{build_navigation, collect_series, highlight_css, parse_about, parse_posts};
+use anyhow::{bail, Context, Result};
 use axum::routing::get;
 use axum::Router;
 use std::{net::SocketAddr, sync::Arc};
@@ -14,13 +18,36 @@ pub async fn run() -> Result<()> {
     let config = SiteConfig::load()
         .context("Failed to load site configuration")?;
 
-    let posts = parse_posts()
+    let posts = parse_posts(&config)
         .context("Failed to parse blog posts during startup")?;
+    let series_map = collect_series(&posts);
+    let navigation = build_navigation(&posts);
 
-    let about_content = parse_about()
+    let about_content = parse_about(&config)
         .context("Failed to parse about page during startup")?;
 
-    let state = Arc::new(AppState { posts, about_content, config: config.clone() });
+    let highlight_css = highlight_css(&config.highlight)
+        .context("Failed to generate code highlighting stylesheet")?;
+    let highlight_css = FingerprintedAsset::new("highlight", "css", highlight_css);
+    let highlight_css_path = highlight_css.path.clone();
+
+    if config.protected.cookie_secret.is_none() && posts.values().any(|post| post.is_protected()) {
+        eprintln!("Warning: no COOKIE_SECRET set, unlocked posts lock again whenever the server restarts");
+    }
+    if config.protected.cookie_max_age().is_none() {
+        bail!("[protected] cookie_days = {} is too large", config.protected.cookie_days);
+    }
+    let unlock_key = UnlockKey::new(config.protected.cookie_secret.as_deref());
+
+    let state = Arc::new(AppState {
+        posts,
+        series: series_map,
+        navigation,
+        about_content,
+        config: config.clone(),
+        highlight_css,
+        unlock_key,
+    });
 
     // Configure rate limiter: 10 requests per second with burst of 20
     // SmartIpKeyExtractor reads X-Forwarded-For header to get real client IP behind Cloudflare
@@ -37,10 +64,14 @@ pub async fn run() -> Result<()> {
         .route("/", get(index))
         .route("/about", get(about))
         .route("/search", get(search))
-        .route("/post/{slug}", get(post))
+        .route("/post/{slug}", get(post).post(unlock))
+        .route("/series/{slug}", get(series))
+        .route("/stats", get(stats))
+        .route("/authors/{id}", get(author))
         .route("/rss.xml", get(rss))
         .route("/sitemap.xml", get(sitemap))
         .route("/robots.txt", get(robots))
+        .route(&highlight_css_path, get(serve_highlight_css))
         .route("/static/{*path}", get(serve_static))
         .fallback(not_found)
         .with_state(state)
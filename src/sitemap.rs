@@ -0,0 +1,92 @@
+//! Which pages go in sitemap.xml. build.rs includes this file through `#[path]` to write the
+//! sitemap, so it may only use std and its build-dependencies (toml).
+
+/// The value of the first `#+KEY:` line in org source, `key` matched ignoring case
+pub fn read_keyword(content: &str, key: &str) -> Option<String> {
+    keyword_values(content, key).next()
+}
+
+/// Values of every `#+KEY:` line in org source
+fn keyword_values<'a>(content: &'a str, key: &'a str) -> impl Iterator<Item = String> + 'a {
+    content.lines().filter_map(move |line| {
+        let (name, value) = line.trim().strip_prefix("#+")?.split_once(':')?;
+        name.eq_ignore_ascii_case(key).then(|| value.trim().to_string())
+    })
+}
+
+/// Whether a post belongs in the sitemap of `domain`: not unlisted, protected or
+/// `#+ROBOTS: noindex`, and without a `#+CANONICAL_URL:` on another site
+pub fn in_sitemap(content: &str, domain: &str) -> bool {
+    let unlisted = read_keyword(content, "visibility").is_some_and(|value| value.eq_ignore_ascii_case("unlisted"));
+    let protected = read_keyword(content, "password_hash").is_some();
+    let noindex = keyword_values(content, "robots").any(|value| {
+        value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|directive| directive.eq_ignore_ascii_case("noindex") || directive.eq_ignore_ascii_case("none"))
+    });
+    let domain = domain.trim_end_matches('/');
+    let canonical_elsewhere = read_keyword(content, "canonical_url").is_some_and(|url| {
+        domain.is_empty() || (url != domain && !url.starts_with(&format!("{}/", domain)))
+    });
+    !unlisted && !protected && !noindex && !canonical_elsewhere
+}
+
+/// Keys of the `[authors]` table in site.toml, sorted; subtables of an author are not ids
+pub fn author_ids(site_toml: &str) -> Result<Vec<String>, toml::de::Error> {
+    let config: toml::Table = site_toml.parse()?;
+    let mut ids: Vec<String> = config
+        .get("authors")
+        .and_then(toml::Value::as_table)
+        .map(|authors| authors.keys().cloned().collect())
+        .unwrap_or_default();
+    ids.sort();
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOMAIN: &str = "https://blog.example.com";
+
+    #[test]
+    fn test_public_post_is_in_sitemap() {
+        assert!(in_sitemap("#+TITLE: Hello\n#+ROBOTS: nofollow\nText\n", DOMAIN));
+    }
+
+    #[test]
+    fn test_hidden_posts_are_left_out() {
+        assert!(!in_sitemap("#+VISIBILITY: unlisted\n", DOMAIN));
+        assert!(!in_sitemap("#+PASSWORD_HASH: $argon2id$v=19$...\n", DOMAIN));
+        assert!(!in_sitemap("#+ROBOTS: nofollow\n#+ROBOTS: NOINDEX\n", DOMAIN));
+        assert!(!in_sitemap("#+robots: none\n", DOMAIN));
+    }
+
+    #[test]
+    fn test_author_ids_ignore_subtables() {
+        let site_toml = r#"
+            [site]
+            name = "Blog"
+
+            [authors.sean]
+            name = "Sean"
+
+            [authors.jane]
+            name = "Jane"
+
+            [authors.jane.links]
+            github = "https://github.com/jane"
+        "#;
+        assert_eq!(author_ids(site_toml).unwrap(), vec!["jane", "sean"]);
+        assert_eq!(author_ids("[site]\nname = \"Blog\"\n").unwrap(), Vec::<String>::new());
+        assert!(author_ids("[authors\n").is_err());
+    }
+
+    #[test]
+    fn test_canonical_url_elsewhere_is_left_out() {
+        assert!(!in_sitemap("#+CANONICAL_URL: https://medium.com/@me/hello\n", DOMAIN));
+        assert!(!in_sitemap("#+CANONICAL_URL: https://blog.example.com.evil.org/hello\n", DOMAIN));
+        assert!(in_sitemap("#+CANONICAL_URL: https://blog.example.com/post/hello\n", DOMAIN));
+        assert!(!in_sitemap("#+CANONICAL_URL: https://blog.example.com/post/hello\n", ""));
+    }
+}
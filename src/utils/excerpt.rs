@@ -1,7 +1,7 @@
-use super::html::strip_html_tags;
+use super::html::{fold_typography, strip_html_tags};
 
 pub fn generate_excerpt(content: &str, query: &str, max_length: usize) -> String {
-    let text = strip_html_tags(content);
+    let text = fold_typography(&strip_html_tags(content));
     let query_lower = query.to_lowercase();
     let text_lower = text.to_lowercase();
 
@@ -13,7 +13,7 @@ pub fn generate_excerpt(content: &str, query: &str, max_length: usize) -> String
             0
         };
 
-        let end = (start + max_length).min(text.len());
+        let end = floor_char_boundary(&text, start + max_length);
         let end = if end < text.len() {
             // Find word boundary after max_length
             text[start..end].rfind(' ').map(|i| start + i).unwrap_or(end)
@@ -31,7 +31,7 @@ pub fn generate_excerpt(content: &str, query: &str, max_length: usize) -> String
         excerpt
     } else {
         // If no match found, return beginning of text
-        let end = max_length.min(text.len());
+        let end = floor_char_boundary(&text, max_length);
         let end = if end < text.len() {
             text[..end].rfind(' ').unwrap_or(end)
         } else {
@@ -45,6 +45,15 @@ pub fn generate_excerpt(content: &str, query: &str, max_length: usize) -> String
     }
 }
 
+/// The largest index up to `index` that does not split a character
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,4 +112,12 @@ mod tests {
         let excerpt = generate_excerpt("", "query", 100);
         assert_eq!(excerpt, "");
     }
+
+    #[test]
+    fn test_generate_excerpt_multibyte_text() {
+        // The cut at max_length would otherwise land inside "é"
+        let excerpt = generate_excerpt("<p>aaaé and more</p>", "", 4);
+        assert_eq!(excerpt, "aaa...");
+        assert_eq!(generate_excerpt("<p>It’s fine</p>", "it's", 100), "It's fine");
+    }
 }
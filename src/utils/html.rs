@@ -9,17 +9,90 @@ static WHITESPACE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\s+").expect("WHITESPACE_REGEX: hardcoded pattern is invalid")
 });
 
+// Code and rendered diagrams, with their content
+static CODE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<pre\b.*?</pre>|<code\b.*?</code>|<svg\b.*?</svg>")
+        .expect("CODE_REGEX: hardcoded pattern is invalid")
+});
+
+// Markup the handler adds around the text: heading permalinks, section numbers and the
+// references list
+static GENERATED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?s)<a class="heading-permalink"[^>]*>.*?</a>|<span class="section-number">.*?</span>|<section class="references-section">.*?</section>"#,
+    )
+    .expect("GENERATED_REGEX: hardcoded pattern is invalid")
+});
+
 pub fn strip_html_tags(html: &str) -> String {
     let without_tags = TAG_REGEX.replace_all(html, " ");
     let decoded = html_escape::decode_html_entities(&without_tags);
     WHITESPACE_REGEX.replace_all(&decoded, " ").trim().to_string()
 }
 
+/// The prose of a rendered post, without code blocks, inline code or diagrams
+pub fn plain_text(html: &str) -> String {
+    fold_typography(&strip_html_tags(&CODE_REGEX.replace_all(html, " ")))
+}
+
+/// Words the author wrote in a rendered post, for reading time and statistics
+/// Punctuation left standing alone, e.g. after removed inline code, is not a word
+pub fn prose_word_count(html: &str) -> usize {
+    plain_text(&GENERATED_REGEX.replace_all(html, " "))
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Undo the `[typography]` pass so search matches what readers type
+/// Soft hyphens are dropped and curly quotes, dashes and ellipses become plain ASCII
+pub fn fold_typography(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{ad}' => {}
+            '‘' | '’' => folded.push('\''),
+            '“' | '”' => folded.push('"'),
+            '–' | '—' => folded.push('-'),
+            '…' => folded.push_str("..."),
+            '\u{a0}' => folded.push(' '),
+            c => folded.push(c),
+        }
+    }
+    folded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn test_plain_text_skips_code() {
+        let html = "<p>Run <code>cargo build</code> first.</p><pre class=\"src\">fn main() {}\n</pre><p>Don’t stop.</p>";
+        assert_eq!(plain_text(html), "Run first. Don't stop.");
+    }
+
+    #[test]
+    fn test_prose_word_count_skips_generated_markup() {
+        let html = "<h1 id=\"intro\"><span class=\"section-number\">1</span> Intro\
+                    <a class=\"heading-permalink\" href=\"#intro\" aria-label=\"Link to this section\">¶</a></h1>\
+                    <p>Three little words, <code>not this</code>.</p>\
+                    <section class=\"references-section\"><h1 id=\"references\">References\
+                    <a class=\"heading-permalink\" href=\"#references\">¶</a></h1>\
+                    <ol class=\"references\"><li>Knuth, The TeXbook, 1984</li></ol></section>";
+        assert_eq!(prose_word_count(html), 4);
+        assert_eq!(prose_word_count("<p>One two</p>"), 2);
+    }
+
+    #[test]
+    fn test_fold_typography() {
+        assert_eq!(
+            fold_typography("“Don’t” ex\u{ad}tra\u{ad}or\u{ad}di\u{ad}nary – 10\u{a0}ms…"),
+            "\"Don't\" extraordinary - 10 ms..."
+        );
+    }
+
     #[test]
     fn test_strip_html_tags_simple() {
         let input = "<p>Hello <strong>world</strong></p>";
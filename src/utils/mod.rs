@@ -1,4 +1,9 @@
 mod html;
 mod excerpt;
+mod unlock;
+mod slug;
 
 pub use excerpt::generate_excerpt;
+pub use html::{fold_typography, plain_text, prose_word_count};
+pub use slug::slugify;
+pub use unlock::{cookie_value, verify_password, UnlockKey, UNLOCK_COOKIE};
@@ -0,0 +1,39 @@
+// Shared with build.rs through `#[path]`, so series slugs in sitemap.xml match the routes; std only
+
+/// Convert text to URL-friendly slug
+/// "What's Nix?" -> "whats-nix"
+/// "Setting Up Direnv" -> "setting-up-direnv"
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c
+            } else if c.is_whitespace() {
+                '-'
+            } else {
+                ' ' // Will be filtered out
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("")
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("What's Nix?"), "whats-nix");
+        assert_eq!(slugify("Setting Up Direnv"), "setting-up-direnv");
+        assert_eq!(slugify("Rust & C++: Part 2 -- Interop!"), "rust-c-part-2-interop");
+    }
+}
@@ -0,0 +1,115 @@
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the cookie that unlocks a password-protected post, scoped to the post's path
+pub const UNLOCK_COOKIE: &str = "post_unlock";
+
+/// Signs and checks the cookies that unlock `#+PASSWORD_HASH:` posts
+#[derive(Clone)]
+pub struct UnlockKey {
+    secret: Vec<u8>,
+}
+
+impl UnlockKey {
+    /// Key from `[protected] cookie_secret`, or a random one that lasts until restart
+    pub fn new(secret: Option<&str>) -> Self {
+        match secret.filter(|secret| !secret.is_empty()) {
+            Some(secret) => Self {
+                secret: secret.as_bytes().to_vec(),
+            },
+            None => {
+                let mut secret = vec![0; 32];
+                OsRng.fill_bytes(&mut secret);
+                Self { secret }
+            }
+        }
+    }
+
+    /// Cookie value for `slug` valid until `expires` (Unix seconds)
+    /// The signature covers the password hash, so changing the password locks the post again
+    pub fn sign(&self, slug: &str, password_hash: &str, expires: i64) -> String {
+        let signature = self.mac(slug, password_hash, expires).finalize().into_bytes();
+        format!("{}.{}", expires, URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Whether a cookie value was signed for this post and has not expired at `now`
+    pub fn verify(&self, value: &str, slug: &str, password_hash: &str, now: i64) -> bool {
+        let Some((expires, signature)) = value.split_once('.') else {
+            return false;
+        };
+        let (Ok(expires), Ok(signature)) = (expires.parse::<i64>(), URL_SAFE_NO_PAD.decode(signature)) else {
+            return false;
+        };
+        expires > now && self.mac(slug, password_hash, expires).verify_slice(&signature).is_ok()
+    }
+
+    fn mac(&self, slug: &str, password_hash: &str, expires: i64) -> HmacSha256 {
+        // Safe expect: HMAC accepts keys of any length
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key of any length is valid");
+        for part in [slug, password_hash, &expires.to_string()] {
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+}
+
+/// Check a submitted password against an argon2 PHC string such as `$argon2id$v=19$...`
+pub fn verify_password(password_hash: &str, password: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// The value of cookie `name` in a `Cookie` request header
+pub fn cookie_value<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::PasswordHasher;
+    use password_hash::SaltString;
+
+    #[test]
+    fn test_signed_cookie_is_scoped_and_expires() {
+        let key = UnlockKey::new(Some("secret"));
+        let value = key.sign("client-a", "$hash", 1_000);
+
+        assert!(key.verify(&value, "client-a", "$hash", 999));
+        assert!(!key.verify(&value, "client-a", "$hash", 1_000), "Expired cookie should fail");
+        assert!(!key.verify(&value, "client-b", "$hash", 999), "Cookie is only for its own post");
+        assert!(!key.verify(&value, "client-a", "$new-hash", 999), "New password should relock");
+        assert!(!UnlockKey::new(Some("other")).verify(&value, "client-a", "$hash", 999));
+        assert!(!key.verify(&value.replace("1000.", "9999."), "client-a", "$hash", 999), "Expiry is signed");
+        assert!(!key.verify("garbage", "client-a", "$hash", 999));
+    }
+
+    #[test]
+    fn test_verify_password() {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(b"hunter2", &salt)
+            .expect("Should hash password")
+            .to_string();
+
+        assert!(verify_password(&hash, "hunter2"));
+        assert!(!verify_password(&hash, "hunter3"));
+        assert!(!verify_password("not a hash", "hunter2"));
+    }
+
+    #[test]
+    fn test_cookie_value() {
+        assert_eq!(cookie_value("theme=dark; post_unlock=1.abc", UNLOCK_COOKIE), Some("1.abc"));
+        assert_eq!(cookie_value("theme=dark", UNLOCK_COOKIE), None);
+    }
+}
@@ -90,7 +90,14 @@ document.addEventListener('DOMContentLoaded', function() {
         // Add click handler
         copyButton.addEventListener('click', function() {
             const code = codeBlock.querySelector('code');
-            const text = code ? code.textContent : codeBlock.textContent;
+            let text = code ? code.textContent : codeBlock.textContent;
+
+            // Console sessions: copy only the commands, not prompts or output
+            if (codeBlock.classList.contains('console')) {
+                text = Array.from(codeBlock.querySelectorAll('.command'))
+                    .map(command => command.textContent)
+                    .join('\n');
+            }
 
             // Clipboard API with fallback
             function copyToClipboard(text) {
@@ -135,6 +142,41 @@ document.addEventListener('DOMContentLoaded', function() {
         });
     });
 
+    // Click-to-load YouTube embeds: nothing is requested from YouTube until the reader asks
+    document.querySelectorAll('.video-embed-load').forEach(function(button) {
+        button.addEventListener('click', function() {
+            const embed = button.closest('.video-embed');
+            const iframe = document.createElement('iframe');
+            iframe.src = `https://www.youtube-nocookie.com/embed/${encodeURIComponent(embed.dataset.youtubeId)}?autoplay=1`;
+            iframe.title = embed.dataset.title;
+            iframe.allow = 'autoplay; encrypted-media; picture-in-picture';
+            iframe.allowFullscreen = true;
+            iframe.referrerPolicy = 'strict-origin-when-cross-origin';
+            embed.replaceChildren(iframe);
+            embed.classList.add('loaded');
+        });
+    });
+
+    // Collapsible sections: unfold every <details> around the anchor being navigated to
+    function openAnchorTarget() {
+        const id = decodeURIComponent(window.location.hash.slice(1));
+        const target = id && document.getElementById(id);
+        if (!target) {
+            return;
+        }
+        let folded = false;
+        for (let details = target.closest('details'); details; details = details.parentElement.closest('details')) {
+            folded = folded || !details.open;
+            details.open = true;
+        }
+        // The browser scrolled before the section was open, so the target was not laid out yet
+        if (folded) {
+            target.scrollIntoView();
+        }
+    }
+    openAnchorTarget();
+    window.addEventListener('hashchange', openAnchorTarget);
+
     // Table of Contents Active Section Highlighting
     const tocLinks = document.querySelectorAll('.toc a');
     if (tocLinks.length > 0) {
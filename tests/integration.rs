@@ -1,5 +1,10 @@
 // Integration tests entry point
 mod integration {
+    mod common;
     mod parsing_tests;
     mod handlers_tests;
+    mod post_tests;
+    mod navigation_tests;
+    mod series_tests;
+    mod stats_tests;
 }
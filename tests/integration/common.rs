@@ -0,0 +1,75 @@
+use axum::{routing::get, Router};
+use axum_test::TestServer;
+use blog_engine::{
+    author, build_navigation, collect_series, highlight_css, index, parse_about, parse_posts, post, rss, search, series,
+    serve_highlight_css, serve_static, stats, unlock, AppState, AuthorConfig, FingerprintedAsset, Post, SeriesInfo,
+    SiteConfig,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Helper function to create test server
+pub async fn create_test_server() -> TestServer {
+    create_test_server_with(|_| {}).await
+}
+
+// Test server whose posts are adjusted by `modify` before the state is built
+pub async fn create_test_server_with(modify: impl FnOnce(&mut HashMap<String, Post>)) -> TestServer {
+    let mut config = SiteConfig::load().expect("Should load config");
+    config.authors.insert(
+        "tester".to_string(),
+        AuthorConfig {
+            name: "Test Author".to_string(),
+            bio: "Writes <em>tests</em>.".to_string(),
+            avatar: None,
+            links: Vec::new(),
+        },
+    );
+    let mut posts = parse_posts(&config).expect("Should parse posts");
+    modify(&mut posts);
+    let series_map = collect_series(&posts);
+    let navigation = build_navigation(&posts);
+    let about_content = parse_about(&config).expect("Should parse about page");
+    let highlight_css = FingerprintedAsset::new(
+        "highlight",
+        "css",
+        highlight_css(&config.highlight).expect("Should build highlight CSS"),
+    );
+    let highlight_css_path = highlight_css.path.clone();
+    let state = Arc::new(AppState::for_tests(posts, series_map, navigation, about_content, config, highlight_css));
+
+    let app = Router::new()
+        .route("/", get(index))
+        .route("/post/{slug}", get(post).post(unlock))
+        .route("/series/{slug}", get(series))
+        .route("/stats", get(stats))
+        .route("/authors/{id}", get(author))
+        .route("/search", get(search))
+        .route("/rss.xml", get(rss))
+        .route(&highlight_css_path, get(serve_highlight_css))
+        .route("/static/{*path}", get(serve_static))
+        .with_state(state);
+
+    TestServer::new(app).expect("Should create test server")
+}
+
+// Test server with the sample post `slug` adjusted by `modify`
+pub async fn create_test_server_with_post(slug: &str, modify: impl FnOnce(&mut Post)) -> TestServer {
+    create_test_server_with(|posts| modify(posts.get_mut(slug).expect("Sample post should exist"))).await
+}
+
+// Both posts in one series, SERIES_PART putting the newer post first
+pub async fn create_series_test_server() -> TestServer {
+    create_test_server_with(|posts| {
+        for (slug, part) in [("nix-on-macos", 1), ("creating-a-blog-with-ai", 2)] {
+            if let Some(post) = posts.get_mut(slug) {
+                post.series = Some(SeriesInfo {
+                    name: "Getting Started".to_string(),
+                    slug: "getting-started".to_string(),
+                    part: Some(part),
+                });
+            }
+        }
+    })
+    .await
+}
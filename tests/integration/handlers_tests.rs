@@ -1,24 +1,5 @@
-use axum::{routing::get, Router};
 use axum::http::StatusCode;
-use axum_test::TestServer;
-use blog_engine::{index, post, search, serve_static, parse_posts, AppState, SiteConfig};
-use std::sync::Arc;
-
-// Helper function to create test server
-async fn create_test_server() -> TestServer {
-    let posts = parse_posts().expect("Should parse posts");
-    let config = SiteConfig::load().expect("Should load config");
-    let state = Arc::new(AppState { posts, config });
-
-    let app = Router::new()
-        .route("/", get(index))
-        .route("/post/{slug}", get(post))
-        .route("/search", get(search))
-        .route("/static/{*path}", get(serve_static))
-        .with_state(state);
-
-    TestServer::new(app).expect("Should create test server")
-}
+use super::common::create_test_server;
 
 #[tokio::test]
 async fn test_index_returns_ok() {
@@ -39,29 +20,16 @@ async fn test_index_contains_html() {
 }
 
 #[tokio::test]
-async fn test_post_valid_slug() {
+async fn test_author_page() {
     let server = create_test_server().await;
-    // Use actual post slug from content/posts/
-    let response = server.get("/post/creating-a-blog-with-ai").await;
+    let response = server.get("/authors/tester").await;
 
     response.assert_status_ok();
-}
+    let html = response.text();
+    assert!(html.contains("<h2>Test Author</h2>"), "Author page should show the name");
+    assert!(html.contains("Writes &#60;em&#62;tests&#60;/em&#62;."), "Bio should be escaped");
 
-#[tokio::test]
-async fn test_post_invalid_slug() {
-    let server = create_test_server().await;
-    let response = server.get("/post/nonexistent-post").await;
-
-    response.assert_status(StatusCode::NOT_FOUND);
-}
-
-#[tokio::test]
-async fn test_post_slug_case_sensitive() {
-    let server = create_test_server().await;
-    // Try with different case - should not find it
-    let response = server.get("/post/Creating-A-Blog-With-AI").await;
-
-    response.assert_status(StatusCode::NOT_FOUND);
+    server.get("/authors/nobody").await.assert_status(StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
@@ -126,3 +94,32 @@ async fn test_serve_static_nonexistent_file() {
 
     response.assert_status(StatusCode::NOT_FOUND);
 }
+
+#[tokio::test]
+async fn test_highlight_css_is_linked_and_served() {
+    let server = create_test_server().await;
+    let page = server.get("/").await.text();
+
+    let href = page
+        .split("href=\"")
+        .map(|rest| rest.split('"').next().unwrap_or_default())
+        .find(|href| href.starts_with("/static/highlight."))
+        .expect("Page should link the highlight stylesheet");
+
+    let response = server.get(href).await;
+    response.assert_status_ok();
+    response.assert_header("content-type", "text/css");
+    assert!(response.text().contains("[data-theme=\"dark\"]"));
+}
+
+#[tokio::test]
+async fn test_rss_includes_inline_styled_content() {
+    let server = create_test_server().await;
+    let response = server.get("/rss.xml").await;
+
+    response.assert_status_ok();
+    let xml = response.text();
+    assert!(xml.contains("<content:encoded>"), "Feed items should carry full content");
+    // Code blocks must not depend on the site stylesheet
+    assert!(xml.contains("<pre class=\"code\" style=\""));
+}
@@ -0,0 +1,15 @@
+use super::common::create_test_server;
+
+#[tokio::test]
+async fn test_post_links_previous_post() {
+    let server = create_test_server().await;
+    let response = server.get("/post/nix-on-macos").await;
+
+    response.assert_status_ok();
+    let html = response.text();
+    assert!(
+        html.contains(r#"<a href="/post/creating-a-blog-with-ai" class="post-navigation-previous" rel="prev">"#),
+        "Post should link the older post as previous"
+    );
+    assert!(!html.contains(r#"rel="next""#), "Newest post should have no next link");
+}
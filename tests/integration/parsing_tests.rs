@@ -1,17 +1,21 @@
-use blog_engine::parse_posts;
+use blog_engine::{parse_posts, SiteConfig};
 use chrono::Datelike;
 
+fn config() -> SiteConfig {
+    SiteConfig::load().expect("Should load config")
+}
+
 #[test]
 fn test_parse_posts_loads_existing_posts() {
     // Will use actual posts in content/posts/
-    let posts = parse_posts().expect("Should parse posts successfully");
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
 
     assert!(!posts.is_empty(), "Should load at least one post");
 }
 
 #[test]
 fn test_parse_posts_extracts_frontmatter() {
-    let posts = parse_posts().expect("Should parse posts successfully");
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
 
     for (slug, post) in posts.iter() {
         // Each post should have extracted metadata
@@ -23,7 +27,7 @@ fn test_parse_posts_extracts_frontmatter() {
 
 #[test]
 fn test_parse_posts_generates_html() {
-    let posts = parse_posts().expect("Should parse posts successfully");
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
 
     for (slug, post) in posts.iter() {
         // HTML should be generated
@@ -40,7 +44,7 @@ fn test_parse_posts_generates_html() {
 
 #[test]
 fn test_parse_posts_lowercase_search_fields() {
-    let posts = parse_posts().expect("Should parse posts successfully");
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
 
     for (slug, post) in posts.iter() {
         // Lowercase fields should be properly generated
@@ -61,7 +65,7 @@ fn test_parse_posts_lowercase_search_fields() {
 
 #[test]
 fn test_parse_posts_slug_format() {
-    let posts = parse_posts().expect("Should parse posts successfully");
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
 
     for (slug, post) in posts.iter() {
         // Slug should be valid (no .org extension, lowercase)
@@ -69,3 +73,36 @@ fn test_parse_posts_slug_format() {
         assert_eq!(post.slug, *slug, "Post slug field should match key");
     }
 }
+
+#[test]
+fn test_parse_posts_feed_content_uses_inline_styles() {
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
+
+    for (slug, post) in posts.iter() {
+        assert!(
+            !post.feed_content.contains("<span class=\"source"),
+            "Post {} feed content should not rely on highlight classes",
+            slug
+        );
+    }
+}
+
+#[test]
+fn test_parse_posts_computes_stats() {
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
+
+    let nix = &posts["nix-on-macos"];
+    assert!(nix.stats.word_count > 0, "Post should have words");
+    assert!(nix.stats.code_blocks > 0, "Nix post has source blocks");
+    assert_eq!(nix.stats.reading_minutes, nix.stats.word_count.div_ceil(200).max(1));
+}
+
+#[test]
+fn test_parse_posts_extracts_summary() {
+    let posts = parse_posts(&config()).expect("Should parse posts successfully");
+
+    for (slug, post) in posts.iter() {
+        assert!(post.summary_html.starts_with("<p>"), "Post {} should fall back to its first paragraph", slug);
+        assert!(!post.content.contains("<!--"), "Post {} content should not keep summary markers", slug);
+    }
+}
@@ -0,0 +1,173 @@
+use axum::http::StatusCode;
+use blog_engine::{Author, Visibility};
+use super::common::{create_test_server, create_test_server_with_post};
+
+#[tokio::test]
+async fn test_post_valid_slug() {
+    let server = create_test_server().await;
+    // Use actual post slug from content/posts/
+    let response = server.get("/post/creating-a-blog-with-ai").await;
+
+    response.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_post_invalid_slug() {
+    let server = create_test_server().await;
+    let response = server.get("/post/nonexistent-post").await;
+
+    response.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_post_with_several_authors() {
+    let server = create_test_server_with_post("nix-on-macos", |post| {
+        post.authors = vec![
+            Author { id: Some("tester".to_string()), name: "Test Author".to_string() },
+            Author { id: None, name: "Guest Writer".to_string() },
+        ];
+    })
+    .await;
+
+    let html = server.get("/post/nix-on-macos").await.text();
+    assert!(
+        html.contains(r#"By <a href="/authors/tester" rel="author">Test Author</a> and Guest Writer</p>"#),
+        "Configured authors link to their page, unknown names are plain text"
+    );
+    assert!(!html.contains("/authors/Guest"));
+
+    let author_page = server.get("/authors/tester").await.text();
+    assert!(author_page.contains("/post/nix-on-macos"), "Author page should list the co-authored post");
+
+    let feed = server.get("/rss.xml").await.text();
+    assert!(feed.contains("<dc:creator>Test Author</dc:creator>"));
+    assert!(feed.contains("<dc:creator>Guest Writer</dc:creator>"));
+}
+
+#[tokio::test]
+async fn test_unlisted_post_is_served_but_not_listed() {
+    let server = create_test_server_with_post("nix-on-macos", |post| {
+        post.visibility = Visibility::Unlisted;
+    })
+    .await;
+
+    let response = server.get("/post/nix-on-macos").await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-robots-tag"), "noindex");
+    assert!(response.text().contains(r#"<meta name="robots" content="noindex">"#));
+
+    let listed = server.get("/post/creating-a-blog-with-ai").await;
+    assert!(listed.maybe_header("x-robots-tag").is_none(), "Listed posts should not be noindex");
+    assert!(!listed.text().contains("/post/nix-on-macos"), "Listed posts should not link to unlisted ones");
+
+    assert!(!server.get("/").await.text().contains("/post/nix-on-macos"), "Index should omit unlisted posts");
+    assert!(!server.get("/rss.xml").await.text().contains("/post/nix-on-macos"), "Feed should omit unlisted posts");
+    assert!(
+        !server.get("/search?q=nix").await.text().contains("/post/nix-on-macos"),
+        "Search should omit unlisted posts"
+    );
+}
+
+#[tokio::test]
+async fn test_link_preview_metadata() {
+    let server = create_test_server_with_post("nix-on-macos", |post| {
+        post.image = Some("https://example.com/hero.png".to_string());
+        post.tags = vec!["nix".to_string()];
+    })
+    .await;
+
+    let post = server.get("/post/nix-on-macos").await.text();
+    assert!(post.contains(r#"<meta property="og:type" content="article">"#));
+    assert!(post.contains(r#"<meta property="og:image" content="https://example.com/hero.png">"#));
+    assert!(post.contains(r#"<meta property="article:tag" content="nix">"#));
+    assert!(post.contains(r#"<meta name="twitter:card" content="summary_large_image">"#));
+    assert!(post.contains(r#""@type":"BlogPosting""#));
+
+    let index = server.get("/").await.text();
+    assert!(index.contains(r#"<meta property="og:type" content="website">"#));
+    assert!(index.contains(r#""@type":"WebSite""#));
+}
+
+#[tokio::test]
+async fn test_post_canonical_url_and_robots() {
+    let server = create_test_server_with_post("nix-on-macos", |post| {
+        post.canonical_url = Some("https://example.com/nix-on-macos".to_string());
+        post.robots = vec!["noindex".to_string(), "nofollow".to_string()];
+    })
+    .await;
+
+    let response = server.get("/post/nix-on-macos").await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-robots-tag"), "noindex, nofollow");
+    let html = response.text();
+    assert!(html.contains(r#"<link rel="canonical" href="https://example.com/nix-on-macos">"#));
+    assert!(html.contains(r#"<meta name="robots" content="noindex, nofollow">"#));
+
+    let listed = server.get("/post/creating-a-blog-with-ai").await.text();
+    let canonical = listed
+        .lines()
+        .find(|line| line.contains(r#"<link rel="canonical""#))
+        .expect("Posts should have a canonical link when a domain is configured");
+    assert!(
+        canonical.contains(r#"/post/creating-a-blog-with-ai">"#),
+        "Posts without #+CANONICAL_URL should point at themselves"
+    );
+}
+
+#[tokio::test]
+async fn test_protected_post_unlocks_with_password() {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2::Argon2::default()
+        .hash_password(b"open sesame", &salt)
+        .expect("Should hash password")
+        .to_string();
+    let server = create_test_server_with_post("nix-on-macos", |post| {
+        post.password_hash = Some(hash);
+        post.description = "Top secret summary".to_string();
+    })
+    .await;
+
+    let locked = server.get("/post/nix-on-macos").await;
+    locked.assert_status_ok();
+    assert_eq!(locked.header("cache-control"), "private, no-store");
+    assert!(locked.text().contains(r#"<form action="/post/nix-on-macos" method="post""#));
+    assert!(!locked.text().contains("post-content"), "Locked post should not include its content");
+    assert!(!locked.text().contains("BlogPosting"), "Locked post should not describe itself in JSON-LD");
+    assert!(!server.get("/").await.text().contains("/post/nix-on-macos"), "Index should omit protected posts");
+
+    let wrong = server
+        .post("/post/nix-on-macos")
+        .form(&[("password", "guess")])
+        .expect_failure()
+        .await;
+    wrong.assert_status(StatusCode::FORBIDDEN);
+    assert!(wrong.text().contains("Wrong password"));
+
+    let unlocked = server.post("/post/nix-on-macos").form(&[("password", "open sesame")]).await;
+    unlocked.assert_status(StatusCode::SEE_OTHER);
+    let set_cookie = unlocked.header("set-cookie");
+    let set_cookie = set_cookie.to_str().expect("Cookie should be ASCII");
+    assert!(set_cookie.contains("Path=/post/nix-on-macos") && set_cookie.contains("HttpOnly"));
+    let cookie = set_cookie.split(';').next().expect("Cookie should have a value").to_string();
+
+    let post = server.get("/post/nix-on-macos").add_header("cookie", cookie.clone()).await;
+    assert!(post.text().contains("post-content"), "Unlocked post should show its content");
+    assert!(
+        !locked.text().contains("Top secret summary") && !post.text().contains("Top secret summary"),
+        "Protected posts keep their description out of meta tags and JSON-LD"
+    );
+
+    let other = server.get("/post/creating-a-blog-with-ai").add_header("cookie", cookie).await;
+    assert!(!other.text().contains("unlock-form"), "Public posts ignore the cookie");
+}
+
+#[tokio::test]
+async fn test_post_slug_case_sensitive() {
+    let server = create_test_server().await;
+    // Try with different case - should not find it
+    let response = server.get("/post/Creating-A-Blog-With-AI").await;
+
+    response.assert_status(StatusCode::NOT_FOUND);
+}
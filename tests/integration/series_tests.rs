@@ -0,0 +1,35 @@
+use axum::http::StatusCode;
+use super::common::{create_test_server, create_series_test_server};
+
+#[tokio::test]
+async fn test_series_box_lists_parts_in_order() {
+    let server = create_series_test_server().await;
+    let html = server.get("/post/creating-a-blog-with-ai").await.text();
+
+    assert!(html.contains(r#"Part 2 of 2 in <a href="/series/getting-started">Getting Started</a>"#));
+    let first = html.find(r#"<li><a href="/post/nix-on-macos">Nix on MacOS</a></li>"#).expect("Part 1 should link");
+    let current = html.find(r#"<li><span aria-current="page">Hello World</span></li>"#).expect("Current part");
+    assert!(first < current, "Parts follow SERIES_PART, not dates");
+    assert!(html.contains(r#""isPartOf":{"@type":"CreativeWorkSeries""#));
+}
+
+#[tokio::test]
+async fn test_series_landing_page() {
+    let server = create_series_test_server().await;
+    let response = server.get("/series/getting-started").await;
+    response.assert_status_ok();
+
+    let html = response.text();
+    assert!(html.contains("<h2>Series: Getting Started</h2>"));
+    let first = html.find(r#"<a href="/post/nix-on-macos">"#).expect("Part 1 should be listed");
+    let second = html.find(r#"<a href="/post/creating-a-blog-with-ai">"#).expect("Part 2 should be listed");
+    assert!(first < second);
+}
+
+#[tokio::test]
+async fn test_series_unknown_slug() {
+    let server = create_test_server().await;
+    let response = server.get("/series/nonexistent-series").await;
+
+    response.assert_status(StatusCode::NOT_FOUND);
+}
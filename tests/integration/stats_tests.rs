@@ -0,0 +1,12 @@
+use super::common::create_test_server;
+
+#[tokio::test]
+async fn test_stats_page() {
+    let server = create_test_server().await;
+    let response = server.get("/stats").await;
+
+    response.assert_status_ok();
+    let html = response.text();
+    assert!(html.contains("<h3>By year</h3>"), "Stats should list posts per year");
+    assert!(html.contains("<td>2025</td>"), "Stats should include the sample posts' year");
+}